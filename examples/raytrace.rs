@@ -0,0 +1,63 @@
+//! Intersects a grid of rays against a sphere on the GPU and compares the hit distances
+//! against the same intersection computed on the CPU, exiting non-zero on any mismatch.
+
+use rustic::{shader_descriptor, GpuContext};
+use shared::{
+    glam::{const_vec3, Vec3, Vec4},
+    math::intersect_sphere,
+    Ray,
+};
+
+const KERNEL: &[u8] = include_bytes!(env!("raytrace.spv"));
+
+// Must match the sphere hardcoded in kernels/raytrace
+const SPHERE_CENTER: Vec3 = const_vec3!([0.0, 0.0, -3.0]);
+const SPHERE_RADIUS: f32 = 1.0;
+
+const WIDTH: usize = 16;
+const HEIGHT: usize = 16;
+
+fn main() {
+    // Orthographic rays looking down -Z over the square [-2, 2] x [-2, 2]
+    let rays = (0..WIDTH * HEIGHT)
+        .map(|i| {
+            let x = ((i % WIDTH) as f32 + 0.5) / WIDTH as f32 * 4.0 - 2.0;
+            let y = ((i / WIDTH) as f32 + 0.5) / HEIGHT as f32 * 4.0 - 2.0;
+            Ray {
+                origin: Vec4::new(x, y, 0.0, 0.0),
+                direction: Vec4::new(0.0, 0.0, -1.0, 0.0),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let result = futures::executor::block_on(async {
+        let context = GpuContext::new().await?;
+        let module = context.try_create_shader_module(&shader_descriptor(KERNEL)?).await?;
+        context
+            .execute_kernel_with_output::<Ray, f32>(&module, &rays, rays.len())
+            .await
+    });
+
+    let hits = match result {
+        Ok(hits) => hits,
+        Err(e) => {
+            eprintln!("Error executing kernel: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let mismatches = rays
+        .iter()
+        .zip(&hits)
+        .filter(|(ray, gpu)| (intersect_sphere(ray, SPHERE_CENTER, SPHERE_RADIUS) - **gpu).abs() > 1e-4)
+        .count();
+
+    for row in hits.chunks(WIDTH) {
+        let line = row.iter().map(|t| if *t < 0.0 { '.' } else { '#' }).collect::<String>();
+        println!("{}", line);
+    }
+    println!("{} of {} rays differ from the CPU reference", mismatches, rays.len());
+    if mismatches > 0 {
+        std::process::exit(1);
+    }
+}
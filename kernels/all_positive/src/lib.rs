@@ -0,0 +1,32 @@
+#![cfg_attr(
+    target_arch = "spirv",
+    feature(register_attr),
+    register_attr(spirv),
+    no_std
+)]
+
+extern crate spirv_std;
+
+use glam::UVec3;
+use shared::DispatchInfo;
+use spirv_std::glam;
+#[cfg(not(target_arch = "spirv"))]
+use spirv_std::macros::spirv;
+
+// Predicate for GpuContext::assert_kernel: clears the flag if any element isn't positive
+#[spirv(compute(threads(64)))]
+pub fn main_cs(
+    #[spirv(global_invocation_id)] id: UVec3,
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 0)] data: &[f32],
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 1)] flag: &mut [u32],
+    #[spirv(uniform, descriptor_set = 0, binding = 2)] info: &DispatchInfo,
+) {
+    let index = info.index(id);
+    if index >= info.len {
+        return;
+    }
+    // Every failing invocation stores the same value, so the race is harmless
+    if !(data[index as usize] > 0.0) {
+        flag[0] = 0;
+    }
+}
@@ -8,6 +8,7 @@
 extern crate spirv_std;
 
 use glam::UVec3;
+use shared::DispatchInfo;
 use spirv_std::glam;
 #[cfg(not(target_arch = "spirv"))]
 use spirv_std::macros::spirv;
@@ -17,7 +18,12 @@ use spirv_std::macros::spirv;
 pub fn main_cs(
     #[spirv(global_invocation_id)] id: UVec3,
     #[spirv(storage_buffer, descriptor_set = 0, binding = 0)] buffer: &mut [u32],
+    #[spirv(uniform, descriptor_set = 0, binding = 1)] info: &DispatchInfo,
 ) {
-    let index = id.x as usize;
+    let index = info.index(id);
+    if index >= info.len {
+        return;
+    }
+    let index = index as usize;
     buffer[index] = (buffer[index] * 2) as u32;
 }
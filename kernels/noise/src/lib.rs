@@ -0,0 +1,32 @@
+#![cfg_attr(
+    target_arch = "spirv",
+    feature(register_attr),
+    register_attr(spirv),
+    no_std
+)]
+
+extern crate spirv_std;
+
+use glam::UVec3;
+use shared::DispatchInfo;
+use spirv_std::glam;
+#[cfg(not(target_arch = "spirv"))]
+use spirv_std::macros::spirv;
+
+// Adds uniform noise in [0, 1) to each element, following the seeding convention of
+// RunOptions::with_seed: one u32 seed per element at the binding after the data
+#[spirv(compute(threads(64)))]
+pub fn main_cs(
+    #[spirv(global_invocation_id)] id: UVec3,
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 0)] buffer: &mut [f32],
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 1)] seeds: &[u32],
+    #[spirv(uniform, descriptor_set = 0, binding = 2)] info: &DispatchInfo,
+) {
+    let index = info.index(id);
+    if index >= info.len {
+        return;
+    }
+    let index = index as usize;
+    // Top 24 bits, which is all the precision an f32 in [0, 1) has
+    buffer[index] += (seeds[index] >> 8) as f32 / 16777216.0;
+}
@@ -0,0 +1,39 @@
+#![cfg_attr(
+    target_arch = "spirv",
+    feature(register_attr),
+    register_attr(spirv),
+    no_std
+)]
+
+extern crate spirv_std;
+
+use glam::UVec3;
+use shared::DispatchInfo;
+use spirv_std::{
+    arch::atomic_i_add,
+    glam,
+    memory::{Scope, Semantics},
+};
+#[cfg(not(target_arch = "spirv"))]
+use spirv_std::macros::spirv;
+
+// Kernel for GpuContext::execute_kernel_with_progress: doubles each element, then bumps the
+// progress counter at binding 1 so the host can see how many elements are done. The host
+// dispatches it in slices, so indices are relative to the slice's offset
+#[spirv(compute(threads(64)))]
+pub fn main_cs(
+    #[spirv(global_invocation_id)] id: UVec3,
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 0)] buffer: &mut [u32],
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 1)] counter: &mut [u32],
+    #[spirv(uniform, descriptor_set = 0, binding = 2)] info: &DispatchInfo,
+) {
+    let index = info.index(id);
+    if index >= info.len {
+        return;
+    }
+    let index = (info.offset + index) as usize;
+    buffer[index] *= 2;
+    unsafe {
+        atomic_i_add::<u32, { Scope::Device as u32 }, { Semantics::NONE.bits() }>(&mut counter[0], 1);
+    }
+}
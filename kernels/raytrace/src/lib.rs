@@ -0,0 +1,33 @@
+#![cfg_attr(
+    target_arch = "spirv",
+    feature(register_attr),
+    register_attr(spirv),
+    no_std
+)]
+
+extern crate spirv_std;
+
+use glam::{UVec3, Vec3};
+use shared::{math::intersect_sphere, DispatchInfo, Ray};
+use spirv_std::glam;
+#[cfg(not(target_arch = "spirv"))]
+use spirv_std::macros::spirv;
+
+const SPHERE_CENTER: Vec3 = glam::const_vec3!([0.0, 0.0, -3.0]);
+const SPHERE_RADIUS: f32 = 1.0;
+
+// LocalSize/numthreads of (x = 64, y = 1, z = 1)
+#[spirv(compute(threads(64)))]
+pub fn main_cs(
+    #[spirv(global_invocation_id)] id: UVec3,
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 0)] rays: &[Ray],
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 1)] hits: &mut [f32],
+    #[spirv(uniform, descriptor_set = 0, binding = 2)] info: &DispatchInfo,
+) {
+    let index = info.index(id);
+    if index >= info.len {
+        return;
+    }
+    let index = index as usize;
+    hits[index] = intersect_sphere(&rays[index], SPHERE_CENTER, SPHERE_RADIUS);
+}
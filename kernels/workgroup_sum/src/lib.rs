@@ -0,0 +1,49 @@
+#![cfg_attr(
+    target_arch = "spirv",
+    feature(register_attr),
+    register_attr(spirv),
+    no_std
+)]
+
+extern crate spirv_std;
+
+use glam::UVec3;
+use shared::DispatchInfo;
+use spirv_std::{arch::workgroup_memory_barrier_with_group_sync, glam};
+#[cfg(not(target_arch = "spirv"))]
+use spirv_std::macros::spirv;
+
+const WORKGROUP_SIZE: usize = 64;
+
+// Replaces the first element of each workgroup's slice with the sum of the slice. Shows the
+// pattern for kernels relying on zeroed workgroup memory: its contents start out undefined, so
+// every invocation zeroes its own slot and waits on a barrier before anything reads it.
+// Invocations past the end of the input then leave their slot at zero.
+#[spirv(compute(threads(64)))]
+pub fn main_cs(
+    #[spirv(global_invocation_id)] id: UVec3,
+    #[spirv(local_invocation_index)] local_index: u32,
+    #[spirv(workgroup)] partial: &mut [f32; WORKGROUP_SIZE],
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 0)] buffer: &mut [f32],
+    #[spirv(uniform, descriptor_set = 0, binding = 1)] info: &DispatchInfo,
+) {
+    let local_index = local_index as usize;
+    partial[local_index] = 0.0;
+    unsafe { workgroup_memory_barrier_with_group_sync() };
+
+    let index = info.index(id);
+    if index < info.len {
+        partial[local_index] = buffer[index as usize];
+    }
+    unsafe { workgroup_memory_barrier_with_group_sync() };
+
+    if local_index == 0 && index < info.len {
+        let mut sum = 0.0;
+        let mut i = 0;
+        while i < WORKGROUP_SIZE {
+            sum += partial[i];
+            i += 1;
+        }
+        buffer[index as usize] = sum;
+    }
+}
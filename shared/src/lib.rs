@@ -0,0 +1,108 @@
+#![no_std]
+
+#[cfg(not(target_arch = "spirv"))]
+extern crate alloc;
+
+pub mod math;
+pub mod pad;
+pub mod std430;
+#[macro_use]
+mod payload;
+
+pub use spirv_std::glam;
+
+// For define_payload!
+#[doc(hidden)]
+pub use bytemuck;
+
+use glam::Vec4;
+
+#[cfg(not(target_arch = "spirv"))]
+use alloc::vec::Vec;
+
+define_payload! {
+    /// A ray laid out as two `Vec4`s so host and shader agree on its 32-byte std430 stride.
+    /// The `w` components are unused.
+    pub struct Ray {
+        pub origin: Vec4,
+        pub direction: Vec4,
+    } = 32;
+}
+
+define_payload! {
+    /// Debug record a kernel can write once per workgroup, from one of its invocations, into the
+    /// buffer `GpuContext::execute_kernel_debug` binds after the data. Entry `i` belongs to the
+    /// workgroup with linear index `i`, `workgroup_id.y * num_workgroups.x + workgroup_id.x`.
+    /// Shaders have no clock, so any timing has to be a count the kernel keeps itself.
+    pub struct WorkgroupDebug {
+        /// Set to anything non-zero by the kernel, to tell written records from untouched ones.
+        pub written: u32,
+        pub first_index: u32,
+        pub last_index: u32,
+        /// Free for kernel-specific counts, e.g. how often a branch was taken.
+        pub counter: u32,
+    } = 16;
+}
+
+/// Splits `rays` into tightly packed origin and direction buffers, for kernels that read them
+/// as separate arrays. Host only.
+#[cfg(not(target_arch = "spirv"))]
+pub fn split_rays(rays: &[Ray]) -> (Vec<Vec4>, Vec<Vec4>) {
+    rays.iter().map(|ray| (ray.origin, ray.direction)).unzip()
+}
+
+/// Inverse of `split_rays`. Host only.
+///
+/// # Panics
+///
+/// If `origins` and `directions` differ in length. `shared` has no error type of its own, so
+/// check the lengths first where they come from outside, as `rustic::soa` does for columns.
+#[cfg(not(target_arch = "spirv"))]
+pub fn join_rays(origins: &[Vec4], directions: &[Vec4]) -> Vec<Ray> {
+    assert_eq!(origins.len(), directions.len(), "every ray needs an origin and a direction");
+    origins
+        .iter()
+        .zip(directions)
+        .map(|(&origin, &direction)| Ray { origin, direction })
+        .collect()
+}
+
+/// Uniform describing a 1D dispatch, bound directly after a kernel's storage buffers.
+/// Large dispatches are packed into Y, so use `index` rather than `id.x` to find the element.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[repr(C)]
+pub struct DispatchInfo {
+    /// Number of elements the dispatch covers; invocations at or past it should do nothing.
+    pub len: u32,
+    /// Invocations per row of the dispatch, i.e. X workgroups times workgroup size.
+    pub x_stride: u32,
+    /// Index of this dispatch's first element in the whole input, when it is split into
+    /// several buffers. Add it to `index` to get a global element index.
+    pub offset: u32,
+    // Kept as a field rather than an array, which would get a 16-byte stride in a uniform
+    _pad0: u32,
+}
+
+// Safe since DispatchInfo is all u32s with the padding spelled out as a field
+unsafe impl bytemuck::Zeroable for DispatchInfo {}
+unsafe impl bytemuck::Pod for DispatchInfo {}
+
+impl DispatchInfo {
+    pub fn new(len: u32, x_stride: u32) -> Self {
+        Self::with_offset(len, x_stride, 0)
+    }
+
+    pub fn with_offset(len: u32, x_stride: u32, offset: u32) -> Self {
+        Self {
+            len,
+            x_stride,
+            offset,
+            _pad0: 0,
+        }
+    }
+
+    /// Linear element index of the invocation with `global_invocation_id` `id`.
+    pub fn index(&self, id: glam::UVec3) -> u32 {
+        id.y * self.x_stride + id.x
+    }
+}
@@ -0,0 +1,105 @@
+//! Geometry helpers shared by kernels and host code, so CPU reference implementations compute
+//! exactly what the GPU does. Only `core` and glam are used, so they build for both targets.
+
+use spirv_std::{
+    glam::{Mat4, Vec3},
+    num_traits::Float,
+};
+
+use crate::Ray;
+
+/// Distance along `ray` to its nearest hit on the sphere at `center` with `radius`, or -1 for a
+/// miss, including spheres entirely behind the ray. A ray starting inside the sphere hits it
+/// on the way out.
+pub fn intersect_sphere(ray: &Ray, center: Vec3, radius: f32) -> f32 {
+    let origin = ray.origin.truncate();
+    let direction = ray.direction.truncate();
+    let oc = origin - center;
+    let a = direction.dot(direction);
+    let b = oc.dot(direction);
+    let c = oc.dot(oc) - radius * radius;
+    let discriminant = b * b - a * c;
+    if discriminant < 0.0 {
+        return -1.0;
+    }
+    let root = discriminant.sqrt();
+    let near = (-b - root) / a;
+    if near >= 0.0 {
+        return near;
+    }
+    let far = (-b + root) / a;
+    if far >= 0.0 {
+        far
+    } else {
+        -1.0
+    }
+}
+
+/// Transforms `ray` by `transform`, treating the origin as a point and the direction as a
+/// vector so translations only move the origin. The direction isn't renormalized.
+pub fn transform_ray(transform: &Mat4, ray: &Ray) -> Ray {
+    Ray {
+        origin: transform.transform_point3(ray.origin.truncate()).extend(0.0),
+        direction: transform.transform_vector3(ray.direction.truncate()).extend(0.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spirv_std::glam::{const_vec3, Vec4};
+
+    const CENTER: Vec3 = const_vec3!([0.0, 0.0, -3.0]);
+
+    fn ray(origin: Vec3, direction: Vec3) -> Ray {
+        Ray {
+            origin: origin.extend(0.0),
+            direction: direction.extend(0.0),
+        }
+    }
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 1e-5, "expected {}, got {}", expected, actual);
+    }
+
+    #[test]
+    fn hit() {
+        assert_close(intersect_sphere(&ray(Vec3::ZERO, -Vec3::Z), CENTER, 1.0), 2.0);
+        // Unnormalized directions give distances in units of the direction's length
+        assert_close(intersect_sphere(&ray(Vec3::ZERO, -Vec3::Z * 2.0), CENTER, 1.0), 1.0);
+    }
+
+    #[test]
+    fn miss() {
+        assert_close(intersect_sphere(&ray(Vec3::new(2.0, 0.0, 0.0), -Vec3::Z), CENTER, 1.0), -1.0);
+        // Sphere entirely behind the ray
+        assert_close(intersect_sphere(&ray(Vec3::ZERO, Vec3::Z), CENTER, 1.0), -1.0);
+    }
+
+    #[test]
+    fn tangent() {
+        assert_close(intersect_sphere(&ray(Vec3::new(1.0, 0.0, 0.0), -Vec3::Z), CENTER, 1.0), 3.0);
+    }
+
+    #[test]
+    fn origin_inside() {
+        assert_close(intersect_sphere(&ray(CENTER, -Vec3::Z), CENTER, 1.0), 1.0);
+        assert_close(intersect_sphere(&ray(CENTER + Vec3::new(0.0, 0.0, 0.5), Vec3::Z), CENTER, 1.0), 0.5);
+    }
+
+    #[test]
+    fn transform_ray_moves_origin_only() {
+        let transform = Mat4::from_translation(Vec3::new(1.0, 2.0, 3.0));
+        let moved = transform_ray(&transform, &ray(Vec3::ZERO, -Vec3::Z));
+        assert_eq!(moved.origin, Vec4::new(1.0, 2.0, 3.0, 0.0));
+        assert_eq!(moved.direction, Vec4::new(0.0, 0.0, -1.0, 0.0));
+    }
+
+    #[test]
+    fn transform_ray_rotates_both() {
+        let transform = Mat4::from_rotation_y(core::f32::consts::FRAC_PI_2);
+        let rotated = transform_ray(&transform, &ray(Vec3::new(0.0, 0.0, 1.0), -Vec3::Z));
+        assert!(rotated.origin.abs_diff_eq(Vec4::new(1.0, 0.0, 0.0, 0.0), 1e-6));
+        assert!(rotated.direction.abs_diff_eq(Vec4::new(-1.0, 0.0, 0.0, 0.0), 1e-6));
+    }
+}
@@ -0,0 +1,66 @@
+//! Wrappers that give 3-component vectors the 16-byte size and alignment std430 expects,
+//! so they can be embedded in structs shared with the GPU without shifting later fields.
+
+use spirv_std::glam::{UVec3, Vec3};
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[repr(C, align(16))]
+pub struct PaddedVec3 {
+    pub v: Vec3,
+    _pad: f32,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[repr(C, align(16))]
+pub struct PaddedUVec3 {
+    pub v: UVec3,
+    _pad: u32,
+}
+
+// Size assertions, an array length mismatch fails to compile
+const _: [(); 16] = [(); core::mem::size_of::<PaddedVec3>()];
+const _: [(); 16] = [(); core::mem::align_of::<PaddedVec3>()];
+const _: [(); 16] = [(); core::mem::size_of::<PaddedUVec3>()];
+const _: [(); 16] = [(); core::mem::align_of::<PaddedUVec3>()];
+
+// Safe since both types are plain old data with the padding spelled out as a field
+unsafe impl bytemuck::Zeroable for PaddedVec3 {}
+unsafe impl bytemuck::Pod for PaddedVec3 {}
+unsafe impl bytemuck::Zeroable for PaddedUVec3 {}
+unsafe impl bytemuck::Pod for PaddedUVec3 {}
+
+impl PaddedVec3 {
+    pub fn new(v: Vec3) -> Self {
+        Self { v, _pad: 0.0 }
+    }
+}
+
+impl PaddedUVec3 {
+    pub fn new(v: UVec3) -> Self {
+        Self { v, _pad: 0 }
+    }
+}
+
+impl From<Vec3> for PaddedVec3 {
+    fn from(v: Vec3) -> Self {
+        Self::new(v)
+    }
+}
+
+impl From<PaddedVec3> for Vec3 {
+    fn from(p: PaddedVec3) -> Self {
+        p.v
+    }
+}
+
+impl From<UVec3> for PaddedUVec3 {
+    fn from(v: UVec3) -> Self {
+        Self::new(v)
+    }
+}
+
+impl From<PaddedUVec3> for UVec3 {
+    fn from(p: PaddedUVec3) -> Self {
+        p.v
+    }
+}
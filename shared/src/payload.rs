@@ -0,0 +1,84 @@
+//! `define_payload!` declares a struct shared between host and kernels with the derives and
+//! layout guarantees every GPU payload needs, so none of them can be forgotten.
+
+/// Defines a `#[repr(C)]` struct deriving `Copy`, `Clone`, `Debug`, `Default` and `PartialEq`,
+/// and implements `Pod`, `Zeroable` and `Std430` for it, so every field type must implement
+/// `Std430` too. The struct's size must be given after `=`, and compilation fails if it differs
+/// or if the fields leave implicit padding, which would make the `Pod` impl unsound. Spell
+/// padding out as fields instead, or use the `pad` wrappers.
+///
+/// ```
+/// use shared::{define_payload, glam::Vec3};
+///
+/// define_payload! {
+///     pub struct Sphere {
+///         pub center: Vec3,
+///         pub radius: f32,
+///     } = 16;
+/// }
+///
+/// let sphere = Sphere { center: Vec3::ZERO, radius: 1.0 };
+/// println!("{:?}", sphere);
+/// ```
+#[macro_export]
+macro_rules! define_payload {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $($(#[$field_meta:meta])* $field_vis:vis $field:ident : $ty:ty),* $(,)?
+        } = $size:expr;
+    ) => {
+        $(#[$meta])*
+        #[derive(Copy, Clone, Debug, Default, PartialEq)]
+        #[repr(C)]
+        $vis struct $name {
+            $($(#[$field_meta])* $field_vis $field: $ty),*
+        }
+
+        // Size assertion, then a check that the fields add up to the whole struct
+        const _: [(); $size] = [(); core::mem::size_of::<$name>()];
+        const _: [(); $size] = [(); 0 $(+ core::mem::size_of::<$ty>())*];
+
+        // Safe since the fields are Pod, checked below, and leave no padding
+        unsafe impl $crate::bytemuck::Zeroable for $name {}
+        unsafe impl $crate::bytemuck::Pod for $name {}
+
+        const _: fn() = || {
+            fn assert_pod<T: $crate::bytemuck::Pod>() {}
+            $(assert_pod::<$ty>();)*
+        };
+
+        impl $crate::std430::Std430 for $name {
+            const STD430_ALIGN: usize =
+                $crate::std430::max_align(&[$(<$ty as $crate::std430::Std430>::STD430_ALIGN),*]);
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::format;
+
+    define_payload! {
+        /// Three u32s spelled out to a 16-byte stride, as the kernels expect.
+        pub struct TestVec {
+            pub x: u32,
+            pub y: u32,
+            pub z: u32,
+            pub _pad: u32,
+        } = 16;
+    }
+
+    #[test]
+    fn derives_debug() {
+        let v = TestVec { x: 1, y: 2, z: 3, _pad: 0 };
+        assert_eq!(format!("{:?}", v), "TestVec { x: 1, y: 2, z: 3, _pad: 0 }");
+    }
+
+    #[test]
+    fn is_pod_and_std430() {
+        let v = TestVec { x: 1, y: 2, z: 3, _pad: 0 };
+        assert_eq!(bytemuck::cast::<TestVec, [u32; 4]>(v), [1, 2, 3, 0]);
+        assert_eq!(<TestVec as crate::std430::Std430>::STD430_ALIGN, 4);
+    }
+}
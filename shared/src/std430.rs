@@ -0,0 +1,43 @@
+//! std430 alignments of the types shared with kernels, so the host can check that its array
+//! stride for a payload matches what the shader expects.
+
+use spirv_std::glam::{IVec2, IVec3, IVec4, Mat4, UVec2, UVec3, UVec4, Vec2, Vec3, Vec4};
+
+use crate::pad::{PaddedUVec3, PaddedVec3};
+
+/// A type with a known std430 base alignment. Structs get the largest alignment of their
+/// members, and an array of them the stride of their size rounded up to that. Implemented by
+/// `define_payload!` for the structs it defines.
+pub trait Std430 {
+    const STD430_ALIGN: usize;
+}
+
+macro_rules! impl_std430 {
+    ($align:expr => $($ty:ty),*) => {
+        $(impl Std430 for $ty {
+            const STD430_ALIGN: usize = $align;
+        })*
+    };
+}
+
+impl_std430!(4 => u32, i32, f32);
+impl_std430!(8 => f64, Vec2, UVec2, IVec2);
+// 3-component vectors align like 4-component ones, which is where host strides go wrong
+impl_std430!(16 => Vec3, UVec3, IVec3, Vec4, UVec4, IVec4, Mat4, PaddedVec3, PaddedUVec3);
+
+impl<T: Std430, const N: usize> Std430 for [T; N] {
+    const STD430_ALIGN: usize = T::STD430_ALIGN;
+}
+
+/// Largest of `aligns`, for computing a struct's alignment from its members'.
+pub const fn max_align(aligns: &[usize]) -> usize {
+    let mut max = 1;
+    let mut i = 0;
+    while i < aligns.len() {
+        if aligns[i] > max {
+            max = aligns[i];
+        }
+        i += 1;
+    }
+    max
+}
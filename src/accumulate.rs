@@ -0,0 +1,51 @@
+use bytemuck::Pod;
+
+use std::mem::size_of;
+
+use crate::{context::storage_entries, ComputeError, GpuContext};
+
+impl GpuContext {
+    /// Runs the kernel `iterations` times, accumulating into a persistent buffer that is only
+    /// read back at the end, for Monte Carlo style progressive kernels.
+    ///
+    /// The kernel reads `input` at binding 0 and adds its samples into one `O` per input element
+    /// at binding 1. Each iteration gets `seed_per_iter(i)` as a u32 push constant, so the
+    /// context must be created with `Features::PUSH_CONSTANTS`.
+    pub async fn accumulate<I: Pod, O: Pod>(
+        &self,
+        module: &wgpu::ShaderModule,
+        input: &[I],
+        iterations: usize,
+        seed_per_iter: impl Fn(usize) -> u32,
+    ) -> Result<Vec<O>, ComputeError> {
+        if !self.device.features().contains(wgpu::Features::PUSH_CONSTANTS) {
+            return Err(ComputeError::UnsupportedFeatures(wgpu::Features::PUSH_CONSTANTS));
+        }
+
+        let bind_group_layout = self.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: None,
+            entries: &storage_entries(&[true, false]),
+        });
+        let compute_pipeline =
+            self.create_pipeline_with_push_constants(module, &[&bind_group_layout], size_of::<u32>() as u32);
+
+        let input_buffer = self.create_input_buffer(input);
+        let accumulation_buffer = self.create_output_buffer::<O>(input.len());
+        let (dims, info_buffer) = self.create_dispatch_info(input.len())?;
+        let bind_group = self.create_bind_group(&bind_group_layout, &[&input_buffer, &accumulation_buffer, &info_buffer]);
+
+        // Record every iteration into one submission, with a pass per iteration so each sees the
+        // previous one's writes
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        for iteration in 0..iterations {
+            let mut cpass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None });
+            cpass.set_pipeline(&compute_pipeline);
+            cpass.set_bind_group(0, &bind_group, &[]);
+            cpass.set_push_constants(0, bytemuck::bytes_of(&seed_per_iter(iteration)));
+            cpass.dispatch(dims.0, dims.1, dims.2);
+        }
+        self.queue.submit(Some(encoder.finish()));
+
+        self.read_buffer(&accumulation_buffer, input.len()).await
+    }
+}
@@ -0,0 +1,202 @@
+use bytemuck::Pod;
+
+use std::time::{Duration, Instant};
+
+use crate::{
+    context::storage_entries, dispatch::MAX_WORKGROUP_SIZE, ComputeError, GpuContext, PassDescriptor, TimestampWrites,
+};
+
+/// One compiled variant of a kernel, e.g. an entry point declared with `#[spirv(compute(threads(N)))]`.
+#[derive(Clone, Copy, Debug)]
+pub struct WorkgroupVariant<'a> {
+    pub workgroup_size: u32,
+    pub entry_point: &'a str,
+}
+
+/// The variants whose workgroup size is within `max_workgroup_size`, or `MAX_WORKGROUP_SIZE`
+/// if that's `None`. wgpu 0.9 accepts pipelines over the adapter's invocation limit, so this
+/// is the only check a variant gets before it is dispatched.
+fn usable_variants<'a>(variants: &[WorkgroupVariant<'a>], max_workgroup_size: Option<u32>) -> Vec<WorkgroupVariant<'a>> {
+    let limit = max_workgroup_size.unwrap_or(MAX_WORKGROUP_SIZE);
+    variants
+        .iter()
+        .filter(|variant| {
+            let usable = variant.workgroup_size > 0 && variant.workgroup_size <= limit;
+            if !usable {
+                log::info!(
+                    "Workgroup size {} ({}) is outside the limit of {}",
+                    variant.workgroup_size,
+                    variant.entry_point,
+                    limit
+                );
+            }
+            usable
+        })
+        .copied()
+        .collect()
+}
+
+impl GpuContext {
+    /// Like `execute_kernel`, picking the largest workgroup size among `variants` within
+    /// `max_workgroup_size` whose pipeline the adapter accepts, so one shader module can carry
+    /// fallbacks for adapters with lower invocation limits. Pass the adapter's real limit when
+    /// known; `None` assumes the guaranteed `MAX_WORKGROUP_SIZE`. Returns the result along with
+    /// the workgroup size that was used.
+    pub async fn execute_kernel_adaptive<T: Pod>(
+        &self,
+        module: &wgpu::ShaderModule,
+        input: &[T],
+        variants: &[WorkgroupVariant<'_>],
+        max_workgroup_size: Option<u32>,
+    ) -> Result<(Vec<T>, u32), ComputeError> {
+        let bind_group_layout = self.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: None,
+            entries: &storage_entries(&[false]),
+        });
+
+        let mut variants = usable_variants(variants, max_workgroup_size);
+        variants.sort_by(|a, b| b.workgroup_size.cmp(&a.workgroup_size));
+
+        let mut selected = None;
+        for variant in variants {
+            // Catch the validation error instead of letting it reach the uncaptured error handler
+            self.device.push_error_scope(wgpu::ErrorFilter::Validation);
+            let compute_pipeline = self.create_pipeline_for_entry(module, &[&bind_group_layout], variant.entry_point, 0);
+            match self.device.pop_error_scope().await {
+                None => {
+                    selected = Some((compute_pipeline, variant.workgroup_size));
+                    break;
+                }
+                Some(e) => log::info!(
+                    "Workgroup size {} ({}) rejected: {}",
+                    variant.workgroup_size,
+                    variant.entry_point,
+                    e
+                ),
+            }
+        }
+        let (compute_pipeline, workgroup_size) = selected.ok_or(ComputeError::NoUsableWorkgroupSize)?;
+
+        let storage_buffer = self.create_storage_buffer(input);
+        let (dims, info_buffer) = self.create_dispatch_info_sized(input.len(), workgroup_size)?;
+        let bind_group = self.create_bind_group(&bind_group_layout, &[&storage_buffer, &info_buffer]);
+
+        self.dispatch(&compute_pipeline, &[&bind_group], dims);
+        let result = self.read_buffer(&storage_buffer, input.len()).await?;
+        Ok((result, workgroup_size))
+    }
+
+    /// Times the kernel over `input` at each of `variants` and returns the fastest workgroup
+    /// size, for picking a size on the adapter at hand. wgpu 0.9 has no specialization
+    /// constants, so each size needs its own entry point as with `execute_kernel_adaptive`.
+    ///
+    /// Each variant gets `warmup` discarded dispatches first. The measured dispatch is timed
+    /// with timestamp queries when the context has `Features::TIMESTAMP_QUERY`, and by wall
+    /// clock around a blocking submit otherwise, which also counts submission overhead.
+    /// Variants over `max_workgroup_size` are skipped as for `execute_kernel_adaptive`, as are
+    /// variants the adapter rejects.
+    pub async fn autotune<T: Pod>(
+        &self,
+        module: &wgpu::ShaderModule,
+        input: &[T],
+        variants: &[WorkgroupVariant<'_>],
+        max_workgroup_size: Option<u32>,
+        warmup: usize,
+    ) -> Result<u32, ComputeError> {
+        let bind_group_layout = self.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: None,
+            entries: &storage_entries(&[false]),
+        });
+        let query_set = if self.device.features().contains(wgpu::Features::TIMESTAMP_QUERY) {
+            Some(self.create_timestamp_query_set(2)?)
+        } else {
+            None
+        };
+
+        let mut fastest: Option<(u32, Duration)> = None;
+        for variant in usable_variants(variants, max_workgroup_size) {
+            self.device.push_error_scope(wgpu::ErrorFilter::Validation);
+            let compute_pipeline = self.create_pipeline_for_entry(module, &[&bind_group_layout], variant.entry_point, 0);
+            if let Some(e) = self.device.pop_error_scope().await {
+                log::info!("Workgroup size {} ({}) rejected: {}", variant.workgroup_size, variant.entry_point, e);
+                continue;
+            }
+
+            // Fresh input per variant, since kernels run in place
+            let storage_buffer = self.create_storage_buffer(input);
+            let (dims, info_buffer) = self.create_dispatch_info_sized(input.len(), variant.workgroup_size)?;
+            let bind_group = self.create_bind_group(&bind_group_layout, &[&storage_buffer, &info_buffer]);
+
+            for _ in 0..warmup {
+                self.dispatch(&compute_pipeline, &[&bind_group], dims);
+            }
+            self.device.poll(wgpu::Maintain::Wait);
+
+            let duration = match &query_set {
+                Some(query_set) => {
+                    let pass = PassDescriptor {
+                        label: None,
+                        timestamp_writes: Some(TimestampWrites {
+                            query_set,
+                            beginning_index: 0,
+                            end_index: 1,
+                        }),
+                    };
+                    self.dispatch_pass(&compute_pipeline, &[&bind_group], dims, &pass);
+                    let timestamps = self.read_timestamps(query_set, 2).await?;
+                    let ticks = timestamps[1].saturating_sub(timestamps[0]);
+                    Duration::from_nanos((ticks as f64 * self.timestamp_period() as f64) as u64)
+                }
+                None => {
+                    let start = Instant::now();
+                    self.dispatch(&compute_pipeline, &[&bind_group], dims);
+                    self.device.poll(wgpu::Maintain::Wait);
+                    start.elapsed()
+                }
+            };
+            log::info!("Workgroup size {} took {:?}", variant.workgroup_size, duration);
+
+            if fastest.map_or(true, |(_, best)| duration < best) {
+                fastest = Some((variant.workgroup_size, duration));
+            }
+        }
+        fastest
+            .map(|(workgroup_size, _)| workgroup_size)
+            .ok_or(ComputeError::NoUsableWorkgroupSize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variant(workgroup_size: u32) -> WorkgroupVariant<'static> {
+        WorkgroupVariant {
+            workgroup_size,
+            entry_point: "main_cs",
+        }
+    }
+
+    fn sizes(variants: &[WorkgroupVariant<'_>]) -> Vec<u32> {
+        variants.iter().map(|variant| variant.workgroup_size).collect()
+    }
+
+    #[test]
+    fn default_limit_drops_large_variants() {
+        let variants = [variant(1024), variant(256), variant(64), variant(512)];
+        assert_eq!(sizes(&usable_variants(&variants, None)), [256, 64]);
+    }
+
+    #[test]
+    fn explicit_limit() {
+        let variants = [variant(1024), variant(256), variant(64)];
+        assert_eq!(sizes(&usable_variants(&variants, Some(1024))), [1024, 256, 64]);
+        assert_eq!(sizes(&usable_variants(&variants, Some(128))), [64]);
+        assert!(usable_variants(&variants, Some(32)).is_empty());
+    }
+
+    #[test]
+    fn zero_size_is_never_usable() {
+        assert!(usable_variants(&[variant(0)], None).is_empty());
+    }
+}
@@ -0,0 +1,104 @@
+//! Sizing helpers for copies with alignment requirements. Buffer-to-buffer copies need sizes
+//! that are multiples of `wgpu::COPY_BUFFER_ALIGNMENT`, while texture copies additionally need
+//! each row padded to `wgpu::COPY_BYTES_PER_ROW_ALIGNMENT` (256 bytes).
+
+use crate::ComputeError;
+
+/// Rounds `size` up to the next multiple of `alignment`, which must be a power of two.
+pub fn align_to(size: u64, alignment: u64) -> u64 {
+    (size + alignment - 1) & !(alignment - 1)
+}
+
+/// Size of a buffer holding `size` bytes that can be copied to or from without violating
+/// `COPY_BUFFER_ALIGNMENT`.
+pub fn copy_size(size: u64) -> u64 {
+    align_to(size, wgpu::COPY_BUFFER_ALIGNMENT)
+}
+
+/// Row pitch for a texture copy with `unpadded_bytes_per_row` bytes of data per row.
+pub fn padded_bytes_per_row(unpadded_bytes_per_row: u32) -> u32 {
+    align_to(unpadded_bytes_per_row as u64, wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as u64) as u32
+}
+
+/// Strips the row padding from `rows` rows of `padded` data laid out with `padded_bytes_per_row`.
+/// The last row needn't be padded, but fails with `LengthMismatch` if `padded` ends before the
+/// data in it does.
+pub fn trim_padded_rows(padded: &[u8], unpadded_bytes_per_row: u32, rows: u32) -> Result<Vec<u8>, ComputeError> {
+    let pitch = padded_bytes_per_row(unpadded_bytes_per_row) as usize;
+    let unpadded = unpadded_bytes_per_row as usize;
+    let required = match rows as usize {
+        0 => 0,
+        rows => (rows - 1) * pitch + unpadded,
+    };
+    if padded.len() < required {
+        return Err(ComputeError::LengthMismatch {
+            expected: required,
+            actual: padded.len(),
+        });
+    }
+    // Empty rows have a pitch of zero too, which `chunks` can't split by
+    if unpadded == 0 {
+        return Ok(Vec::new());
+    }
+    Ok(padded
+        .chunks(pitch)
+        .take(rows as usize)
+        .flat_map(|row| &row[..unpadded])
+        .copied()
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pad_rows(data: &[u8], unpadded_bytes_per_row: u32) -> Vec<u8> {
+        let pitch = padded_bytes_per_row(unpadded_bytes_per_row) as usize;
+        data.chunks(unpadded_bytes_per_row as usize)
+            .flat_map(|row| {
+                let mut padded = row.to_vec();
+                padded.resize(pitch, 0xaa);
+                padded
+            })
+            .collect()
+    }
+
+    #[test]
+    fn pad_trim_round_trip() {
+        for &width in &[1u32, 4, 255, 256, 257, 1000] {
+            for &rows in &[1u32, 3] {
+                let data = (0..width * rows).map(|i| i as u8).collect::<Vec<_>>();
+                let padded = pad_rows(&data, width);
+                assert_eq!(padded.len(), (padded_bytes_per_row(width) * rows) as usize);
+                assert_eq!(trim_padded_rows(&padded, width, rows).unwrap(), data, "width {}", width);
+
+                // Dropping the padding after the last row still trims cleanly
+                let pitch = padded_bytes_per_row(width) as usize;
+                let short = &padded[..(rows as usize - 1) * pitch + width as usize];
+                assert_eq!(trim_padded_rows(short, width, rows).unwrap(), data, "width {}", width);
+            }
+        }
+    }
+
+    #[test]
+    fn short_last_row_is_an_error() {
+        let padded = pad_rows(&[7; 300], 100);
+        let truncated = &padded[..2 * 256 + 99];
+        assert!(matches!(
+            trim_padded_rows(truncated, 100, 3),
+            Err(ComputeError::LengthMismatch { expected: 612, actual: 611 })
+        ));
+        assert!(trim_padded_rows(&[], 100, 1).is_err());
+    }
+
+    #[test]
+    fn no_rows() {
+        assert!(trim_padded_rows(&[], 100, 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn empty_rows() {
+        assert!(trim_padded_rows(&[], 0, 3).unwrap().is_empty());
+        assert!(trim_padded_rows(&[1, 2, 3], 0, 3).unwrap().is_empty());
+    }
+}
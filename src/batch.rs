@@ -0,0 +1,189 @@
+use bytemuck::Pod;
+
+use std::{collections::VecDeque, mem::size_of};
+
+use crate::{align, context::elements_from_bytes, ComputeError, ErrorKind, GpuContext, RunOptions, TrackedBuffer};
+
+/// Outcome of `GpuContext::execute_kernel_batch_retrying`, which doesn't fail the whole batch
+/// over inputs that keep failing.
+#[derive(Debug)]
+pub struct BatchResults<T> {
+    /// Output for each input, in input order, or `None` where every attempt failed.
+    pub results: Vec<Option<Vec<T>>>,
+    /// Index and last error of each input whose output is missing.
+    pub failed: Vec<(usize, ComputeError)>,
+}
+
+impl GpuContext {
+    /// Runs the kernel in place over each of `inputs`, submitting each as soon as it is
+    /// recorded rather than waiting for the previous readback. At most `options.max_in_flight`
+    /// submissions are outstanding at once; past that, the oldest is read back first.
+    pub async fn execute_kernel_batch<T: Pod>(
+        &self,
+        module: &wgpu::ShaderModule,
+        inputs: &[&[T]],
+        options: &RunOptions,
+    ) -> Result<Vec<Vec<T>>, ComputeError> {
+        let (bind_group_layout, compute_pipeline) = self.create_storage_pipeline(module, &[false]);
+        let max_in_flight = if options.max_in_flight == 0 {
+            usize::MAX
+        } else {
+            options.max_in_flight
+        };
+
+        let mut results = Vec::with_capacity(inputs.len());
+        let mut in_flight = VecDeque::new();
+        for &input in inputs {
+            if in_flight.len() >= max_in_flight {
+                let (readback_buffer, future, len) = in_flight.pop_front().unwrap();
+                results.push(self.finish_readback(&readback_buffer, future, len).await?);
+            }
+
+            let (readback_buffer, future) = self.submit_in_place(&bind_group_layout, &compute_pipeline, input)?;
+            in_flight.push_back((readback_buffer, future, input.len()));
+        }
+
+        for (readback_buffer, future, len) in in_flight {
+            results.push(self.finish_readback(&readback_buffer, future, len).await?);
+        }
+        Ok(results)
+    }
+
+    /// Like `execute_kernel_batch`, re-submitting only the inputs whose runs failed with a
+    /// runtime error, such as a readback that never mapped, up to `options.retries` times.
+    /// Inputs still failing after that are reported in `BatchResults::failed` while the others
+    /// keep their results. Errors of other kinds still fail the whole batch, since retrying
+    /// can't fix them.
+    pub async fn execute_kernel_batch_retrying<T: Pod>(
+        &self,
+        module: &wgpu::ShaderModule,
+        inputs: &[&[T]],
+        options: &RunOptions,
+    ) -> Result<BatchResults<T>, ComputeError> {
+        let (bind_group_layout, compute_pipeline) = self.create_storage_pipeline(module, &[false]);
+        let max_in_flight = if options.max_in_flight == 0 {
+            usize::MAX
+        } else {
+            options.max_in_flight
+        };
+
+        let mut results = (0..inputs.len()).map(|_| None).collect::<Vec<_>>();
+        let mut pending = (0..inputs.len()).collect::<Vec<_>>();
+        let mut failed = Vec::new();
+        for attempt in 0..=options.retries {
+            if pending.is_empty() {
+                break;
+            }
+            if attempt > 0 {
+                log::warn!("Retrying {} failed inputs, attempt {}", pending.len(), attempt);
+            }
+
+            failed.clear();
+            let mut in_flight = VecDeque::new();
+            let mut outcomes = Vec::with_capacity(pending.len());
+            for &index in &pending {
+                if in_flight.len() >= max_in_flight {
+                    let (index, readback_buffer, future, len) = in_flight.pop_front().unwrap();
+                    outcomes.push((index, self.finish_readback(&readback_buffer, future, len).await));
+                }
+                let input = inputs[index];
+                let (readback_buffer, future) = self.submit_in_place(&bind_group_layout, &compute_pipeline, input)?;
+                in_flight.push_back((index, readback_buffer, future, input.len()));
+            }
+            for (index, readback_buffer, future, len) in in_flight {
+                outcomes.push((index, self.finish_readback(&readback_buffer, future, len).await));
+            }
+
+            pending.clear();
+            for (index, outcome) in outcomes {
+                match outcome {
+                    Ok(output) => results[index] = Some(output),
+                    Err(e) if e.kind() == ErrorKind::Runtime => {
+                        pending.push(index);
+                        failed.push((index, e));
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+        Ok(BatchResults { results, failed })
+    }
+
+    /// Runs the kernel in place over each of `inputs` and passes every result through `post`,
+    /// overlapping the GPU work for one input with `post` on the previous one. Two submissions
+    /// are in flight at most.
+    ///
+    /// Overlap needs the GPU to make progress while `post` runs, so use `PollMode::Poll` or a
+    /// background poller; `PollMode::Wait` waits for both submissions before each `post`.
+    pub async fn pipeline_map<T: Pod, R>(
+        &self,
+        module: &wgpu::ShaderModule,
+        inputs: &[&[T]],
+        mut post: impl FnMut(Vec<T>) -> R,
+    ) -> Result<Vec<R>, ComputeError> {
+        let (bind_group_layout, compute_pipeline) = self.create_storage_pipeline(module, &[false]);
+
+        let mut results = Vec::with_capacity(inputs.len());
+        let mut previous = None;
+        for &input in inputs {
+            // Submit this input before post-processing the last one
+            let (readback_buffer, future) = self.submit_in_place(&bind_group_layout, &compute_pipeline, input)?;
+            if let Some((readback_buffer, future, len)) = previous.take() {
+                results.push(post(self.finish_readback(&readback_buffer, future, len).await?));
+            }
+            previous = Some((readback_buffer, future, input.len()));
+        }
+        if let Some((readback_buffer, future, len)) = previous {
+            results.push(post(self.finish_readback(&readback_buffer, future, len).await?));
+        }
+        Ok(results)
+    }
+
+    /// Runs a pipeline made by `create_storage_pipeline(module, &[false])` over `input` with the
+    /// readback copy in the same submission, returning the readback buffer and its pending map.
+    pub(crate) fn submit_in_place<T: Pod>(
+        &self,
+        bind_group_layout: &wgpu::BindGroupLayout,
+        compute_pipeline: &wgpu::ComputePipeline,
+        input: &[T],
+    ) -> Result<
+        (
+            TrackedBuffer,
+            impl std::future::Future<Output = Result<(), wgpu::BufferAsyncError>>,
+        ),
+        ComputeError,
+    > {
+        let storage_buffer = self.create_storage_buffer(input);
+        let (dims, info_buffer) = self.create_dispatch_info(input.len())?;
+        let bind_group = self.create_bind_group(bind_group_layout, &[&storage_buffer, &info_buffer]);
+        let copy_size = align::copy_size((input.len() * size_of::<T>()) as u64);
+        let readback_buffer = self.create_readback_buffer(copy_size.max(wgpu::COPY_BUFFER_ALIGNMENT));
+
+        // Record the dispatch and its readback copy into one submission
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        {
+            let mut cpass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None });
+            cpass.set_pipeline(compute_pipeline);
+            cpass.set_bind_group(0, &bind_group, &[]);
+            cpass.dispatch(dims.0, dims.1, dims.2);
+        }
+        if copy_size > 0 {
+            encoder.copy_buffer_to_buffer(&storage_buffer, 0, &readback_buffer, 0, copy_size);
+        }
+        self.queue.submit(Some(encoder.finish()));
+
+        let future = readback_buffer.slice(..).map_async(wgpu::MapMode::Read);
+        Ok((readback_buffer, future))
+    }
+
+    /// Waits for a pending `map_async` on `readback_buffer` and copies out its first `len` `T`s.
+    pub(crate) async fn finish_readback<T: Pod>(
+        &self,
+        readback_buffer: &TrackedBuffer,
+        future: impl std::future::Future<Output = Result<(), wgpu::BufferAsyncError>>,
+        len: usize,
+    ) -> Result<Vec<T>, ComputeError> {
+        self.finish_map(readback_buffer, (len * size_of::<T>()) as u64, future, elements_from_bytes)
+            .await?
+    }
+}
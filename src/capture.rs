@@ -0,0 +1,48 @@
+use bytemuck::Pod;
+
+use std::{fs, mem::size_of, path::Path};
+
+use crate::{context::elements_from_bytes, shader_descriptor, ComputeError, GpuContext, RunOptions};
+
+const SHADER_FILE: &str = "shader.spv";
+const INPUT_FILE: &str = "input.bin";
+const OUTPUT_FILE: &str = "output.bin";
+const INFO_FILE: &str = "info.txt";
+
+impl GpuContext {
+    /// Runs the kernel in `spirv` over `input` like `execute_kernel`, and writes everything needed
+    /// to reproduce the run to the directory `dir`: the SPIR-V blob, the raw input and output,
+    /// and a text summary of the element size, adapter and run stats.
+    pub async fn capture<T: Pod>(&self, spirv: &[u8], input: &[T], dir: &Path) -> Result<Vec<T>, ComputeError> {
+        fs::create_dir_all(dir)?;
+        fs::write(dir.join(SHADER_FILE), spirv)?;
+        fs::write(dir.join(INPUT_FILE), bytemuck::cast_slice(input))?;
+
+        let module = self.try_create_shader_module(&shader_descriptor(spirv)?).await?;
+        let (output, stats) = self
+            .execute_kernel_with_stats(&module, input, &RunOptions::default())
+            .await?;
+
+        fs::write(dir.join(OUTPUT_FILE), bytemuck::cast_slice(&output))?;
+        let info = format!(
+            "element_size={}\n{:#?}\n{}\n",
+            size_of::<T>(),
+            self.adapter_info(),
+            stats
+        );
+        fs::write(dir.join(INFO_FILE), info)?;
+        Ok(output)
+    }
+}
+
+/// Re-runs a capture written by `GpuContext::capture` on `context`, returning the output that
+/// was recorded alongside the output of the new run so they can be compared.
+pub async fn replay<T: Pod>(context: &GpuContext, dir: &Path) -> Result<(Vec<T>, Vec<T>), ComputeError> {
+    let spirv = fs::read(dir.join(SHADER_FILE))?;
+    let input = elements_from_bytes::<T>(&fs::read(dir.join(INPUT_FILE))?)?;
+    let recorded = elements_from_bytes::<T>(&fs::read(dir.join(OUTPUT_FILE))?)?;
+
+    let module = context.try_create_shader_module(&shader_descriptor(&spirv)?).await?;
+    let output = context.execute_kernel(&module, &input).await?;
+    Ok((recorded, output))
+}
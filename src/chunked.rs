@@ -0,0 +1,159 @@
+use bytemuck::Pod;
+
+use std::{mem::size_of, sync::mpsc::Sender};
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use crate::{dispatch::WORKGROUP_SIZE, ComputeError, GpuContext};
+
+/// Cloneable flag for abandoning a chunked run from another thread or task. A dispatch can't
+/// be interrupted, so cancellation takes effect at the next chunk boundary.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+impl GpuContext {
+    /// Like `execute_kernel`, but uploads, dispatches and reads back `input` in chunks of at
+    /// most `chunk_len` elements, reusing one pipeline for all of them.
+    pub async fn execute_kernel_chunked<T: Pod>(
+        &self,
+        module: &wgpu::ShaderModule,
+        input: &[T],
+        chunk_len: usize,
+    ) -> Result<Vec<T>, ComputeError> {
+        self.execute_kernel_cancellable(module, input, chunk_len, &CancellationToken::new())
+            .await
+    }
+
+    /// Like `execute_kernel_chunked`, returning `ComputeError::Cancelled` at the first chunk
+    /// boundary after `token` is cancelled.
+    pub async fn execute_kernel_cancellable<T: Pod>(
+        &self,
+        module: &wgpu::ShaderModule,
+        input: &[T],
+        chunk_len: usize,
+        token: &CancellationToken,
+    ) -> Result<Vec<T>, ComputeError> {
+        let (bind_group_layout, compute_pipeline) = self.create_storage_pipeline(module, &[false]);
+
+        let chunk_len = chunk_len.max(1);
+        let mut result = Vec::with_capacity(input.len());
+        for (index, chunk) in input.chunks(chunk_len).enumerate() {
+            if token.is_cancelled() {
+                return Err(ComputeError::Cancelled);
+            }
+            let offset = index * chunk_len;
+            result.extend(self.run_chunk(&bind_group_layout, &compute_pipeline, chunk, offset).await?);
+        }
+        Ok(result)
+    }
+
+    /// Like `execute_kernel_chunked`, also sending a copy of each chunk's result through `sender`
+    /// as soon as it is read back, so another thread can start consuming before the run ends.
+    /// A disconnected receiver doesn't stop the run.
+    pub async fn execute_kernel_chunked_to<T: Pod + Send>(
+        &self,
+        module: &wgpu::ShaderModule,
+        input: &[T],
+        chunk_len: usize,
+        sender: &Sender<Vec<T>>,
+    ) -> Result<Vec<T>, ComputeError> {
+        let (bind_group_layout, compute_pipeline) = self.create_storage_pipeline(module, &[false]);
+
+        let chunk_len = chunk_len.max(1);
+        let mut result = Vec::with_capacity(input.len());
+        for (index, chunk) in input.chunks(chunk_len).enumerate() {
+            let output = self
+                .run_chunk(&bind_group_layout, &compute_pipeline, chunk, index * chunk_len)
+                .await?;
+            let _ = sender.send(output.clone());
+            result.extend(output);
+        }
+        Ok(result)
+    }
+
+    /// Like `execute_kernel_chunked`, for stencil kernels whose outputs depend on neighbouring
+    /// inputs. Each chunk is uploaded with up to `halo` extra elements of its neighbours on each
+    /// side, clamped at the ends of the input, and those are trimmed from its output again, so
+    /// elements near chunk boundaries see the same neighbours as in a single run. `halo` should
+    /// be at least the stencil radius. `DispatchInfo::offset` is the global index of the first
+    /// uploaded element, halo included.
+    pub async fn execute_kernel_chunked_halo<T: Pod>(
+        &self,
+        module: &wgpu::ShaderModule,
+        input: &[T],
+        chunk_len: usize,
+        halo: usize,
+    ) -> Result<Vec<T>, ComputeError> {
+        let (bind_group_layout, compute_pipeline) = self.create_storage_pipeline(module, &[false]);
+
+        let chunk_len = chunk_len.max(1);
+        let mut result = Vec::with_capacity(input.len());
+        let mut start = 0;
+        while start < input.len() {
+            let end = (start + chunk_len).min(input.len());
+            let upload_start = start.saturating_sub(halo);
+            let upload_end = (end + halo).min(input.len());
+
+            let output = self
+                .run_chunk(&bind_group_layout, &compute_pipeline, &input[upload_start..upload_end], upload_start)
+                .await?;
+            result.extend_from_slice(&output[start - upload_start..end - upload_start]);
+            start = end;
+        }
+        Ok(result)
+    }
+
+    /// Like `execute_kernel`, but splits inputs too large for one storage buffer binding into
+    /// the largest chunks the device allows. Each chunk's `DispatchInfo::offset` holds the
+    /// index of its first element, for kernels that need global indices.
+    pub async fn execute_kernel_streamed<T: Pod>(
+        &self,
+        module: &wgpu::ShaderModule,
+        input: &[T],
+    ) -> Result<Vec<T>, ComputeError> {
+        self.execute_kernel_chunked(module, input, self.max_chunk_len::<T>()).await
+    }
+
+    /// Largest number of `T`s that fit in one storage buffer binding, rounded down to whole
+    /// workgroups so that only the final chunk has a partial one.
+    fn max_chunk_len<T>(&self) -> usize {
+        let max_bytes = self.device.limits().max_storage_buffer_binding_size as usize;
+        let len = max_bytes / size_of::<T>().max(1);
+        let workgroup = WORKGROUP_SIZE as usize;
+        (len / workgroup * workgroup).max(1)
+    }
+
+    /// Runs one chunk, starting at element `offset` of the whole input, through a pipeline made
+    /// by `create_storage_pipeline(module, &[false])`.
+    pub(crate) async fn run_chunk<T: Pod>(
+        &self,
+        bind_group_layout: &wgpu::BindGroupLayout,
+        compute_pipeline: &wgpu::ComputePipeline,
+        chunk: &[T],
+        offset: usize,
+    ) -> Result<Vec<T>, ComputeError> {
+        let storage_buffer = self.create_storage_buffer(chunk);
+        let (dims, info_buffer) = self.create_dispatch_info_at(chunk.len(), WORKGROUP_SIZE, offset)?;
+        let bind_group = self.create_bind_group(bind_group_layout, &[&storage_buffer, &info_buffer]);
+
+        self.dispatch(compute_pipeline, &[&bind_group], dims);
+        self.read_buffer(&storage_buffer, chunk.len()).await
+    }
+}
@@ -0,0 +1,57 @@
+use bytemuck::Pod;
+
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use crate::{ComputeError, GpuContext, TrackedBuffer};
+
+/// Resolves once the GPU has finished all work submitted before it was created.
+///
+/// wgpu 0.9 has no `Queue::on_submitted_work_done`, so this maps a 4-byte marker buffer copied
+/// to in a submission of its own. Submissions complete in order, so the map resolving means
+/// everything before it has finished too.
+pub struct CompletionFuture<'a>(Pin<Box<dyn Future<Output = Result<(), ComputeError>> + 'a>>);
+
+impl<'a> Future for CompletionFuture<'a> {
+    type Output = Result<(), ComputeError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.0.as_mut().poll(cx)
+    }
+}
+
+impl GpuContext {
+    /// Returns a future that resolves when all work submitted so far has completed.
+    pub fn completion(&self) -> CompletionFuture<'_> {
+        let source = self.create_output_buffer::<u32>(1);
+        let marker = self.create_readback_buffer(wgpu::COPY_BUFFER_ALIGNMENT);
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        encoder.copy_buffer_to_buffer(&source, 0, &marker, 0, wgpu::COPY_BUFFER_ALIGNMENT);
+        self.queue.submit(Some(encoder.finish()));
+
+        CompletionFuture(Box::pin(async move {
+            self.map_read(&marker, wgpu::COPY_BUFFER_ALIGNMENT, |_| ()).await?;
+            drop(source);
+            Ok(())
+        }))
+    }
+
+    /// Like `execute_kernel_no_readback`, but returns immediately with a `CompletionFuture`
+    /// instead of blocking until the kernel finishes.
+    pub fn execute_kernel_deferred<T: Pod>(
+        &self,
+        module: &wgpu::ShaderModule,
+        input: &[T],
+    ) -> Result<(TrackedBuffer, CompletionFuture<'_>), ComputeError> {
+        let (bind_group_layout, compute_pipeline) = self.create_storage_pipeline(module, &[false]);
+        let storage_buffer = self.create_storage_buffer(input);
+        let (dims, info_buffer) = self.create_dispatch_info(input.len())?;
+        let bind_group = self.create_bind_group(&bind_group_layout, &[&storage_buffer, &info_buffer]);
+
+        self.dispatch(&compute_pipeline, &[&bind_group], dims);
+        Ok((storage_buffer, self.completion()))
+    }
+}
@@ -0,0 +1,1155 @@
+use wgpu::util::DeviceExt;
+
+use std::{
+    borrow::Cow,
+    mem::size_of,
+    num::{NonZeroU32, NonZeroU64},
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use bytemuck::Pod;
+
+use shared::DispatchInfo;
+
+use crate::{
+    align,
+    dispatch::{dispatch_count, WORKGROUP_SIZE},
+    layout::check_element_layout,
+    poller::{self, Poller},
+    seed::seeds,
+    timing::PassDescriptor,
+    tracker::{ResourceTracker, TrackedBuffer},
+    validate::uninitialized_workgroup_reads,
+    Backends, CompatibleSurface, ComputeError, ComputeOptions, PollMode, ReadbackStrategy, RunOptions, RunStats,
+};
+
+use futures::FutureExt;
+
+pub struct GpuContext {
+    pub(crate) device: Arc<wgpu::Device>,
+    pub(crate) queue: wgpu::Queue,
+    poller: Option<Poller>,
+    poll_mode: PollMode,
+    max_poll_iters: usize,
+    map_direct: bool,
+    debug_checks: bool,
+    pub(crate) deterministic: bool,
+    empty_bindings: bool,
+    pub(crate) tracker: Arc<ResourceTracker>,
+    pub(crate) belt: Mutex<wgpu::util::StagingBelt>,
+    adapter_info: wgpu::AdapterInfo,
+}
+
+impl GpuContext {
+    pub async fn new() -> Result<Self, ComputeError> {
+        Self::with_options(&ComputeOptions::default()).await
+    }
+
+    /// Creates a context whose device has `features` enabled, failing if the adapter lacks any of them.
+    pub async fn with_features(features: wgpu::Features) -> Result<Self, ComputeError> {
+        Self::with_options(&ComputeOptions {
+            features,
+            ..Default::default()
+        })
+        .await
+    }
+
+    pub async fn with_options(options: &ComputeOptions<'_>) -> Result<Self, ComputeError> {
+        let mut features = options.features;
+
+        let adapter = match options.compatible_surface {
+            Some(target) => select_surface_adapter(target).await?,
+            None if !features.is_empty() || options.required_limits.is_some() => {
+                select_capable_adapter(&options.backends, features, options.required_limits.as_ref())?
+            }
+            None => select_adapter(&options.backends).await?,
+        };
+
+        // Mapping storage buffers directly needs them to be mappable, which is an extension
+        let map_direct = match options.readback {
+            ReadbackStrategy::Copy => false,
+            ReadbackStrategy::MapDirect => true,
+            ReadbackStrategy::Auto => {
+                adapter.get_info().device_type == wgpu::DeviceType::IntegratedGpu
+                    && adapter.features().contains(wgpu::Features::MAPPABLE_PRIMARY_BUFFERS)
+            }
+        };
+        if map_direct {
+            features |= wgpu::Features::MAPPABLE_PRIMARY_BUFFERS;
+        }
+
+        let missing = features - adapter.features();
+        if !missing.is_empty() {
+            return Err(ComputeError::UnsupportedFeatures(missing));
+        }
+
+        // Push constants are useless without raising the default size limit of zero
+        let mut limits = options.required_limits.clone().unwrap_or_default();
+        if features.contains(wgpu::Features::PUSH_CONSTANTS) {
+            limits.max_push_constant_size = adapter.limits().max_push_constant_size;
+        }
+
+        // Use adapter to create device and command queue
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    label: None,
+                    features,
+                    limits,
+                },
+                None,
+            )
+            .await?;
+
+        let empty_bindings = options.allow_empty_bindings && supports_empty_bindings(&device).await;
+
+        // Having the feature doesn't guarantee the backend accepts the usage combination
+        let map_direct = if map_direct && !supports_map_direct(&device).await {
+            log::warn!("Adapter rejected MAP_READ | STORAGE buffers, falling back to copied readback");
+            false
+        } else {
+            map_direct
+        };
+        log::info!(
+            "Reading back results {}",
+            if map_direct { "by mapping storage buffers directly" } else { "through a copy" }
+        );
+
+        let context = Self {
+            device: Arc::new(device),
+            queue,
+            poller: None,
+            poll_mode: options.poll_mode,
+            max_poll_iters: options.max_poll_iters,
+            map_direct,
+            debug_checks: options.debug_checks,
+            deterministic: options.deterministic,
+            empty_bindings,
+            tracker: Arc::default(),
+            belt: Mutex::new(wgpu::util::StagingBelt::new(BELT_CHUNK_SIZE)),
+            adapter_info: adapter.get_info(),
+        };
+        if !context.is_hardware() {
+            log::warn!(
+                "Running on software adapter {}, expect much lower performance",
+                context.adapter_info.name
+            );
+        }
+        Ok(context)
+    }
+
+    pub fn adapter_info(&self) -> &wgpu::AdapterInfo {
+        &self.adapter_info
+    }
+
+    /// Whether `execute_kernel_with` maps storage buffers directly instead of copying them out.
+    pub fn uses_direct_mapping(&self) -> bool {
+        self.map_direct
+    }
+
+    /// False when running on a software renderer such as lavapipe or WARP, which can be orders of
+    /// magnitude slower than real hardware.
+    pub fn is_hardware(&self) -> bool {
+        !matches!(self.adapter_info.device_type, wgpu::DeviceType::Cpu | wgpu::DeviceType::Other)
+    }
+
+    /// The context's device, for custom wgpu work sharing this context's resources.
+    pub fn device(&self) -> &wgpu::Device {
+        &self.device
+    }
+
+    pub fn queue(&self) -> &wgpu::Queue {
+        &self.queue
+    }
+
+    /// Starts a background thread that keeps the device polled, so readbacks from overlapping
+    /// kernel calls resolve without each one blocking on `Maintain::Wait`.
+    pub fn start_poller(&mut self) {
+        if self.poller.is_none() {
+            self.poller = Some(Poller::start(self.device.clone()));
+        }
+    }
+
+    /// Stops the background poller, returning to blocking polls on readback.
+    pub fn stop_poller(&mut self) {
+        self.poller = None;
+    }
+
+    /// Creates a shader module, with naga's validation forced on if the context was created
+    /// with `debug_checks`. Debug checks also warn about SPIR-V kernels that may read
+    /// workgroup memory before writing it.
+    pub fn create_shader_module(&self, shader_binary: &wgpu::ShaderModuleDescriptor) -> wgpu::ShaderModule {
+        if !self.debug_checks {
+            return self.device.create_shader_module(shader_binary);
+        }
+        let source = match &shader_binary.source {
+            wgpu::ShaderSource::SpirV(words) => {
+                warn_uninitialized_workgroup_reads(shader_binary.label, words);
+                wgpu::ShaderSource::SpirV(Cow::Borrowed(words))
+            }
+            wgpu::ShaderSource::Wgsl(code) => wgpu::ShaderSource::Wgsl(Cow::Borrowed(code)),
+        };
+        self.device.create_shader_module(&wgpu::ShaderModuleDescriptor {
+            label: shader_binary.label,
+            source,
+            flags: shader_binary.flags | wgpu::ShaderFlags::VALIDATION,
+        })
+    }
+
+    /// Uploads `contents` into a buffer usable as a storage binding and as a copy source for readback.
+    pub fn create_storage_buffer<T: Pod>(&self, contents: &[T]) -> TrackedBuffer {
+        self.create_buffer_with_usage(contents, STORAGE_USAGE)
+    }
+
+    /// Uploads `contents` into a buffer for a read-only storage binding. It is never copied
+    /// from, so it skips `COPY_SRC` and the driver is free to place it optimally.
+    pub fn create_input_buffer<T: Pod>(&self, contents: &[T]) -> TrackedBuffer {
+        self.create_buffer_with_usage(contents, READ_ONLY_USAGE)
+    }
+
+    pub(crate) fn create_buffer_with_usage<T: Pod>(&self, contents: &[T], usage: wgpu::BufferUsage) -> TrackedBuffer {
+        self.tracker.track(self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: None,
+            contents: bytemuck::cast_slice(contents),
+            usage,
+        }))
+    }
+
+    /// Like `create_buffer_with_usage`, sized for `capacity` elements with `contents` at the
+    /// start and the rest zeroed. `capacity` must be at least `contents.len()`.
+    pub(crate) fn create_buffer_with_capacity<T: Pod>(&self, contents: &[T], capacity: usize, usage: wgpu::BufferUsage) -> TrackedBuffer {
+        if capacity == contents.len() {
+            return self.create_buffer_with_usage(contents, usage);
+        }
+        let buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: align::copy_size((capacity * size_of::<T>()) as u64),
+            usage,
+            mapped_at_creation: true,
+        });
+        let bytes = bytemuck::cast_slice(contents);
+        buffer.slice(..).get_mapped_range_mut()[..bytes.len()].copy_from_slice(bytes);
+        buffer.unmap();
+        self.tracker.track(buffer)
+    }
+
+    /// Creates a zeroed storage buffer large enough for `len` elements of `T`.
+    pub(crate) fn create_output_buffer<T: Pod>(&self, len: usize) -> TrackedBuffer {
+        self.tracker.track(self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: align::copy_size((len * size_of::<T>()) as u64),
+            usage: STORAGE_USAGE,
+            mapped_at_creation: false,
+        }))
+    }
+
+    pub async fn execute_kernel<T: Pod>(&self, module: &wgpu::ShaderModule, input: &[T]) -> Result<Vec<T>, ComputeError> {
+        self.execute_kernel_with(module, input, &RunOptions::default()).await
+    }
+
+    pub async fn execute_kernel_with<T: Pod>(
+        &self,
+        module: &wgpu::ShaderModule,
+        input: &[T],
+        options: &RunOptions,
+    ) -> Result<Vec<T>, ComputeError> {
+        Ok(self.execute_kernel_with_stats(module, input, options).await?.0)
+    }
+
+    /// Like `execute_kernel_with`, also returning diagnostics about the run.
+    pub async fn execute_kernel_with_stats<T: Pod>(
+        &self,
+        module: &wgpu::ShaderModule,
+        input: &[T],
+        options: &RunOptions,
+    ) -> Result<(Vec<T>, RunStats), ComputeError> {
+        check_element_layout::<T>(options)?;
+
+        // The storage buffer must stay bindable and either copyable to the readback buffer or
+        // mappable itself
+        let default_usage = if self.map_direct { MAP_DIRECT_USAGE } else { STORAGE_USAGE };
+        let usage = options.usage_override.unwrap_or(default_usage);
+        let readback_usage = if self.map_direct {
+            wgpu::BufferUsage::MAP_READ
+        } else {
+            wgpu::BufferUsage::COPY_SRC
+        };
+        let required = wgpu::BufferUsage::STORAGE | readback_usage;
+        if !usage.contains(required) {
+            return Err(ComputeError::MissingUsage(required - usage));
+        }
+
+        // Append-style kernels write past the input, so the buffer may be larger than it
+        let capacity = options.buffer_capacity.unwrap_or(input.len());
+        if capacity < input.len() {
+            return Err(ComputeError::CapacityTooSmall {
+                capacity,
+                len: input.len(),
+            });
+        }
+
+        let size = (capacity * size_of::<T>()) as u64;
+        let max = self.device.limits().max_storage_buffer_binding_size as u64;
+        if size > max {
+            return Err(ComputeError::BufferTooLarge { size, max });
+        }
+
+        let read_only: &[bool] = if options.seed.is_some() { &[false, true] } else { &[false] };
+        let (bind_group_layout, compute_pipeline) = self.create_storage_pipeline(module, read_only);
+        let seed_buffer = options
+            .seed
+            .map(|seed| self.create_input_buffer(&seeds(seed, input.len().max(1))));
+
+        // Warmup runs get their own copy of the input so the measured run sees it untouched
+        let (dims, info_buffer) = self.create_dispatch_info(input.len())?;
+        let trailing = seed_buffer
+            .iter()
+            .map(|buffer| &**buffer)
+            .chain(Some(&*info_buffer))
+            .collect::<Vec<&wgpu::Buffer>>();
+        for _ in 0..options.warmup {
+            let warmup_buffer = self.create_buffer_with_capacity(input, capacity, usage);
+            let bind_group = self.create_bind_group(&bind_group_layout, &[&[&*warmup_buffer][..], &trailing[..]].concat());
+            self.dispatch_labeled(&compute_pipeline, &[&bind_group], dims, options.label.as_deref());
+            self.device.poll(wgpu::Maintain::Wait);
+        }
+
+        let start = Instant::now();
+
+        // Create buffer for CPU -> GPU and storage
+        let storage_buffer = self.create_buffer_with_capacity(input, capacity, usage);
+        let bind_group = self.create_bind_group(&bind_group_layout, &[&[&*storage_buffer][..], &trailing[..]].concat());
+
+        self.dispatch_labeled(&compute_pipeline, &[&bind_group], dims, options.label.as_deref());
+        let result = if self.map_direct {
+            self.read_mapped(&storage_buffer, capacity).await?
+        } else {
+            self.read_buffer(&storage_buffer, capacity).await?
+        };
+
+        let stats = RunStats {
+            adapter: self.adapter_info.name.clone(),
+            backend: self.adapter_info.backend,
+            elements: input.len(),
+            bytes_uploaded: (input.len() * size_of::<T>()) as u64,
+            bytes_read_back: (capacity * size_of::<T>()) as u64,
+            dispatch: dims,
+            workgroup_size: WORKGROUP_SIZE,
+            duration: start.elapsed(),
+        };
+        Ok((result, stats))
+    }
+
+    /// Binds the same storage buffer at bindings `0..aliases`, with the `DispatchInfo` uniform after
+    /// them, so a kernel can read through one binding and write through another.
+    ///
+    /// Every alias is bound read-write, since wgpu rejects a buffer used as both read-only and
+    /// writable storage in one dispatch. Nothing orders the aliased accesses: an invocation
+    /// reading an element another invocation writes sees either value. Only use this when each
+    /// invocation touches disjoint elements, which is why it requires `allow_aliasing`.
+    pub async fn execute_kernel_aliased<T: Pod>(
+        &self,
+        module: &wgpu::ShaderModule,
+        input: &[T],
+        aliases: usize,
+        options: &RunOptions,
+    ) -> Result<Vec<T>, ComputeError> {
+        if !options.allow_aliasing {
+            return Err(ComputeError::AliasingNotAllowed);
+        }
+
+        let (bind_group_layout, compute_pipeline) = self.create_storage_pipeline(module, &vec![false; aliases]);
+        let storage_buffer = self.create_storage_buffer(input);
+        let (dims, info_buffer) = self.create_dispatch_info(input.len())?;
+        let mut buffers = vec![&*storage_buffer; aliases];
+        buffers.push(&info_buffer);
+        let bind_group = self.create_bind_group(&bind_group_layout, &buffers);
+
+        self.dispatch(&compute_pipeline, &[&bind_group], dims);
+        self.read_buffer(&storage_buffer, input.len()).await
+    }
+
+    /// Like `execute_kernel`, for input that is already raw bytes, e.g. read from a file.
+    ///
+    /// `bytes` holds elements of `elem_size` bytes each, and one invocation is dispatched per
+    /// element, so a length that isn't a whole number of elements is rejected up front.
+    pub async fn execute_kernel_bytes(
+        &self,
+        module: &wgpu::ShaderModule,
+        bytes: &[u8],
+        elem_size: usize,
+    ) -> Result<Vec<u8>, ComputeError> {
+        if elem_size == 0 || bytes.len() % elem_size != 0 {
+            return Err(ComputeError::MisalignedInput {
+                len: bytes.len(),
+                elem_size,
+            });
+        }
+
+        let (bind_group_layout, compute_pipeline) = self.create_storage_pipeline(module, &[false]);
+        let storage_buffer = self.create_storage_buffer(bytes);
+        let (dims, info_buffer) = self.create_dispatch_info(bytes.len() / elem_size)?;
+        let bind_group = self.create_bind_group(&bind_group_layout, &[&storage_buffer, &info_buffer]);
+
+        self.dispatch(&compute_pipeline, &[&bind_group], dims);
+        self.read_buffer(&storage_buffer, bytes.len()).await
+    }
+
+    /// Runs a kernel reading `input` at binding 0 and writing `output_len` elements of `O` at binding 1.
+    ///
+    /// One invocation is dispatched per input element, and only the output buffer is read back.
+    pub async fn execute_kernel_with_output<I: Pod, O: Pod>(
+        &self,
+        module: &wgpu::ShaderModule,
+        input: &[I],
+        output_len: usize,
+    ) -> Result<Vec<O>, ComputeError> {
+        self.execute_kernel_with_output_options(module, input, output_len, &RunOptions::default())
+            .await
+    }
+
+    /// Like `execute_kernel_with_output`, honoring `options.verify_coverage`.
+    pub async fn execute_kernel_with_output_options<I: Pod, O: Pod>(
+        &self,
+        module: &wgpu::ShaderModule,
+        input: &[I],
+        output_len: usize,
+        options: &RunOptions,
+    ) -> Result<Vec<O>, ComputeError> {
+        let (bind_group_layout, compute_pipeline) = self.create_storage_pipeline(module, &[true, false]);
+
+        let input_buffer = self.create_input_buffer(input);
+        let output_buffer = self.create_output_buffer::<O>(output_len);
+        let (dims, info_buffer) = self.create_dispatch_info(input.len())?;
+        let bind_group = self.create_bind_group(&bind_group_layout, &[&input_buffer, &output_buffer, &info_buffer]);
+
+        let sentinel = if options.verify_coverage && output_len > 0 {
+            Some(self.write_sentinel::<O>(&output_buffer, output_len))
+        } else {
+            None
+        };
+
+        self.dispatch_labeled(&compute_pipeline, &[&bind_group], dims, options.label.as_deref());
+        let result = self.read_buffer::<O>(&output_buffer, output_len).await?;
+
+        // If the kernel never wrote the last element, the dispatch didn't cover the whole range
+        if let Some(sentinel) = sentinel {
+            if bytemuck::bytes_of(&result[output_len - 1]) == &sentinel[..] {
+                return Err(ComputeError::IncompleteCoverage);
+            }
+        }
+        Ok(result)
+    }
+
+    /// Fills the last element of `buffer` with a recognizable pattern, returning the bytes the
+    /// element holds afterwards.
+    fn write_sentinel<T: Pod>(&self, buffer: &wgpu::Buffer, len: usize) -> Vec<u8> {
+        const PATTERN: [u8; 4] = 0xDEAD_BEEFu32.to_ne_bytes();
+
+        // Queue writes must start and end on COPY_BUFFER_ALIGNMENT, so cover the aligned range
+        // around the last element
+        let elem_start = ((len - 1) * size_of::<T>()) as u64;
+        let start = elem_start & !(wgpu::COPY_BUFFER_ALIGNMENT - 1);
+        let end = align::copy_size((len * size_of::<T>()) as u64);
+        let bytes = PATTERN
+            .iter()
+            .copied()
+            .cycle()
+            .take((end - start) as usize)
+            .collect::<Vec<_>>();
+        self.queue.write_buffer(buffer, start, &bytes);
+
+        let offset = (elem_start - start) as usize;
+        bytes[offset..offset + size_of::<T>()].to_vec()
+    }
+
+    /// Like `execute_kernel_with_output`, but binding 0 is writable and read back as well, for
+    /// kernels that transform their input in place while also emitting auxiliary output.
+    pub async fn execute_kernel_inout<I: Pod, O: Pod>(
+        &self,
+        module: &wgpu::ShaderModule,
+        input: &[I],
+        output_len: usize,
+    ) -> Result<(Vec<I>, Vec<O>), ComputeError> {
+        let (input, output) = self
+            .execute_kernel_inout_with(module, input, output_len, &RunOptions::default())
+            .await?;
+        Ok((input.unwrap_or_default(), output))
+    }
+
+    /// Like `execute_kernel_inout`, returning `None` instead of reading binding 0 back when
+    /// `options.readback_input` is off.
+    pub async fn execute_kernel_inout_with<I: Pod, O: Pod>(
+        &self,
+        module: &wgpu::ShaderModule,
+        input: &[I],
+        output_len: usize,
+        options: &RunOptions,
+    ) -> Result<(Option<Vec<I>>, Vec<O>), ComputeError> {
+        let (bind_group_layout, compute_pipeline) = self.create_storage_pipeline(module, &[false, false]);
+
+        let input_buffer = self.create_storage_buffer(input);
+        let output_buffer = self.create_output_buffer::<O>(output_len);
+        let (dims, info_buffer) = self.create_dispatch_info(input.len())?;
+        let bind_group = self.create_bind_group(&bind_group_layout, &[&input_buffer, &output_buffer, &info_buffer]);
+
+        self.dispatch_labeled(&compute_pipeline, &[&bind_group], dims, options.label.as_deref());
+        let input = if options.readback_input {
+            Some(self.read_buffer(&input_buffer, input.len()).await?)
+        } else {
+            None
+        };
+        let output = self.read_buffer(&output_buffer, output_len).await?;
+        Ok((input, output))
+    }
+
+    /// Runs a compaction-style kernel and reads back only the elements it reports as valid.
+    ///
+    /// The kernel reads `input` at binding 0, writes up to `input.len()` elements to binding 1
+    /// and stores how many it wrote as a single u32 at binding 2. The count is read back first
+    /// so the second copy only covers `count * size_of::<T>()` bytes.
+    pub async fn execute_kernel_dynamic_readback<T: Pod>(
+        &self,
+        module: &wgpu::ShaderModule,
+        input: &[T],
+    ) -> Result<Vec<T>, ComputeError> {
+        let (bind_group_layout, compute_pipeline) = self.create_storage_pipeline(module, &[true, false, false]);
+
+        let input_buffer = self.create_input_buffer(input);
+        let output_buffer = self.create_output_buffer::<T>(input.len());
+        let count_buffer = self.create_output_buffer::<u32>(1);
+        let (dims, info_buffer) = self.create_dispatch_info(input.len())?;
+        let bind_group = self.create_bind_group(
+            &bind_group_layout,
+            &[&input_buffer, &output_buffer, &count_buffer, &info_buffer],
+        );
+
+        self.dispatch(&compute_pipeline, &[&bind_group], dims);
+
+        // Clamp in case the kernel counted past the end of the output buffer
+        let count = self.read_buffer::<u32>(&count_buffer, 1).await?[0] as usize;
+        self.read_buffer(&output_buffer, count.min(input.len())).await
+    }
+
+    /// Runs the kernel and blocks until the GPU has finished, skipping the readback entirely.
+    ///
+    /// The storage buffer is returned so its contents can stay on the GPU for later kernels.
+    pub fn execute_kernel_no_readback<T: Pod>(&self, module: &wgpu::ShaderModule, input: &[T]) -> Result<TrackedBuffer, ComputeError> {
+        let (bind_group_layout, compute_pipeline) = self.create_storage_pipeline(module, &[false]);
+        let storage_buffer = self.create_storage_buffer(input);
+        let (dims, info_buffer) = self.create_dispatch_info(input.len())?;
+        let bind_group = self.create_bind_group(&bind_group_layout, &[&storage_buffer, &info_buffer]);
+
+        self.dispatch(&compute_pipeline, &[&bind_group], dims);
+        self.device.poll(wgpu::Maintain::Wait);
+        Ok(storage_buffer)
+    }
+
+    /// Dispatches a kernel that declares no buffer bindings and waits for it to finish.
+    ///
+    /// Uses an empty bind group layout when the context was created with `allow_empty_bindings`
+    /// and the adapter accepted one, and the dummy single-buffer layout otherwise.
+    pub fn execute_kernel_no_bindings(&self, module: &wgpu::ShaderModule, workgroups: u32) -> Result<(), ComputeError> {
+        if self.empty_bindings {
+            let bind_group_layout = self.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: None,
+                entries: &[],
+            });
+            let compute_pipeline = self.create_pipeline(module, &[&bind_group_layout]);
+            let bind_group = self.create_bind_group(&bind_group_layout, &[]);
+            self.dispatch(&compute_pipeline, &[&bind_group], (workgroups, 1, 1));
+        } else {
+            // Create dummy bind group layout since some GPUs don't support empty bind layout group
+            let bind_group_layout = self.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: None,
+                entries: &[storage_layout_entry(0, false, None)],
+            });
+            let compute_pipeline = self.create_pipeline(module, &[&bind_group_layout]);
+            let dummy_buffer = self.create_output_buffer::<u32>(1);
+            let bind_group = self.create_bind_group(&bind_group_layout, &[&dummy_buffer]);
+            self.dispatch(&compute_pipeline, &[&bind_group], (workgroups, 1, 1));
+        }
+
+        self.device.poll(wgpu::Maintain::Wait);
+        Ok(())
+    }
+
+    /// Binds `buffers` as a single binding array at binding 0 and dispatches `workgroups` workgroups.
+    ///
+    /// Requires a context created with `Features::BUFFER_BINDING_ARRAY`; results are left in the
+    /// buffers and can be fetched with `read_buffer`. Fails with `EmptyBindingArray` if `buffers`
+    /// is empty, since a zero-length array would be bound as a plain buffer.
+    pub fn execute_kernel_binding_array(
+        &self,
+        module: &wgpu::ShaderModule,
+        buffers: &[&wgpu::Buffer],
+        workgroups: u32,
+    ) -> Result<(), ComputeError> {
+        if buffers.is_empty() {
+            return Err(ComputeError::EmptyBindingArray);
+        }
+        if !self.device.features().contains(wgpu::Features::BUFFER_BINDING_ARRAY) {
+            return Err(ComputeError::UnsupportedFeatures(wgpu::Features::BUFFER_BINDING_ARRAY));
+        }
+
+        let bind_group_layout = self.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: None,
+            entries: &[storage_layout_entry(0, false, NonZeroU32::new(buffers.len() as u32))],
+        });
+        let compute_pipeline = self.create_pipeline(module, &[&bind_group_layout]);
+
+        let bindings = buffers
+            .iter()
+            .map(|buffer| wgpu::BufferBinding {
+                buffer,
+                offset: 0,
+                size: None,
+            })
+            .collect::<Vec<_>>();
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::BufferArray(&bindings),
+            }],
+        });
+
+        self.dispatch(&compute_pipeline, &[&bind_group], (workgroups, 1, 1));
+        self.device.poll(wgpu::Maintain::Wait);
+        Ok(())
+    }
+
+    /// Copies the first `len` elements of `buffer` back to the CPU.
+    pub async fn read_buffer<T: Pod>(&self, buffer: &wgpu::Buffer, len: usize) -> Result<Vec<T>, ComputeError> {
+        // Zero-sized buffers can't be mapped
+        if len == 0 {
+            return Ok(Vec::new());
+        }
+
+        // Fetch result, copying into a typed vec so the mapped bytes needn't be aligned for T
+        self.with_mapped(buffer, (len * size_of::<T>()) as wgpu::BufferAddress, elements_from_bytes)
+            .await?
+    }
+
+    /// Like `read_buffer`, but maps `buffer` itself instead of copying it to a readback buffer.
+    /// `buffer` must have been created with `MAP_READ` usage.
+    pub(crate) async fn read_mapped<T: Pod>(&self, buffer: &wgpu::Buffer, len: usize) -> Result<Vec<T>, ComputeError> {
+        if len == 0 {
+            return Ok(Vec::new());
+        }
+
+        self.map_read(buffer, (len * size_of::<T>()) as wgpu::BufferAddress, elements_from_bytes)
+            .await?
+    }
+
+    /// Copies the first `size` bytes of `buffer` to a readback buffer and calls `f` on the mapped
+    /// bytes, unmapping once it returns. `size` must be non-zero.
+    pub(crate) async fn with_mapped<R>(
+        &self,
+        buffer: &wgpu::Buffer,
+        size: wgpu::BufferAddress,
+        f: impl FnOnce(&[u8]) -> R,
+    ) -> Result<R, ComputeError> {
+        let copy_size = align::copy_size(size);
+
+        let readback_buffer = self.create_readback_buffer(copy_size);
+
+        // CPU readback
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        encoder.copy_buffer_to_buffer(buffer, 0, &readback_buffer, 0, copy_size);
+
+        // Wait for GPU to finish
+        self.queue.submit(Some(encoder.finish()));
+        self.map_read(&readback_buffer, size, f).await
+    }
+
+    /// Maps the first `size` bytes of `buffer`, which must have `MAP_READ` usage and hold at
+    /// least `size` bytes rounded up to `COPY_BUFFER_ALIGNMENT`, and calls `f` on them,
+    /// unmapping once it returns.
+    pub(crate) async fn map_read<R>(
+        &self,
+        buffer: &wgpu::Buffer,
+        size: wgpu::BufferAddress,
+        f: impl FnOnce(&[u8]) -> R,
+    ) -> Result<R, ComputeError> {
+        let mapping = buffer.slice(..mapped_size(size)).map_async(wgpu::MapMode::Read);
+        self.finish_map(buffer, size, mapping, f).await
+    }
+
+    /// Like `map_read`, for a `map_async` of `buffer` the caller already started, which must
+    /// cover the first `size` bytes rounded up to `COPY_BUFFER_ALIGNMENT`.
+    pub(crate) async fn finish_map<R>(
+        &self,
+        buffer: &wgpu::Buffer,
+        size: wgpu::BufferAddress,
+        mapping: impl std::future::Future<Output = Result<(), wgpu::BufferAsyncError>>,
+        f: impl FnOnce(&[u8]) -> R,
+    ) -> Result<R, ComputeError> {
+        self.wait_for(mapping).await?;
+        self.tracker.mapped();
+
+        let data = buffer.slice(..mapped_size(size)).get_mapped_range();
+        let result = f(&data[..size as usize]);
+        drop(data);
+        buffer.unmap();
+        self.tracker.unmapped();
+        Ok(result)
+    }
+
+    /// Creates a buffer for GPU -> CPU copies of `size` bytes, which must be copy-aligned.
+    pub(crate) fn create_readback_buffer(&self, size: wgpu::BufferAddress) -> TrackedBuffer {
+        self.tracker.track(self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size,
+            // Can be read to the CPU, and can be copied from the shader's storage buffer
+            usage: wgpu::BufferUsage::MAP_READ | wgpu::BufferUsage::COPY_DST,
+            mapped_at_creation: false,
+        }))
+    }
+
+    /// Drives the device until `future` resolves, according to the context's `PollMode`.
+    /// Relies on the background poller instead when one is running.
+    pub(crate) async fn wait_for<F>(&self, future: F) -> Result<(), ComputeError>
+    where
+        F: std::future::Future<Output = Result<(), wgpu::BufferAsyncError>>,
+    {
+        futures::pin_mut!(future);
+        if self.poller.is_some() {
+            return Ok(future.await?);
+        }
+        match self.poll_mode {
+            PollMode::Wait => {
+                self.device.poll(wgpu::Maintain::Wait);
+                Ok(future.await?)
+            }
+            PollMode::Poll => {
+                let mut iterations = 0;
+                loop {
+                    self.device.poll(wgpu::Maintain::Poll);
+                    if let Some(output) = future.as_mut().now_or_never() {
+                        return Ok(output?);
+                    }
+                    iterations += 1;
+                    if iterations == self.max_poll_iters {
+                        return Err(ComputeError::PollTimeout { iterations });
+                    }
+                    poller::yield_now().await;
+                }
+            }
+        }
+    }
+
+    pub(crate) fn create_pipeline(&self, module: &wgpu::ShaderModule, bind_group_layouts: &[&wgpu::BindGroupLayout]) -> wgpu::ComputePipeline {
+        self.create_pipeline_with_push_constants(module, bind_group_layouts, 0)
+    }
+
+    /// Like `create_pipeline`, reserving `push_constant_size` bytes of push constants if non-zero.
+    pub(crate) fn create_pipeline_with_push_constants(
+        &self,
+        module: &wgpu::ShaderModule,
+        bind_group_layouts: &[&wgpu::BindGroupLayout],
+        push_constant_size: u32,
+    ) -> wgpu::ComputePipeline {
+        self.create_pipeline_for_entry(module, bind_group_layouts, "main_cs", push_constant_size)
+    }
+
+    pub(crate) fn create_pipeline_for_entry(
+        &self,
+        module: &wgpu::ShaderModule,
+        bind_group_layouts: &[&wgpu::BindGroupLayout],
+        entry_point: &str,
+        push_constant_size: u32,
+    ) -> wgpu::ComputePipeline {
+        let push_constant_range = wgpu::PushConstantRange {
+            stages: wgpu::ShaderStage::COMPUTE,
+            range: 0..push_constant_size,
+        };
+        let push_constant_ranges = if push_constant_size > 0 {
+            std::slice::from_ref(&push_constant_range)
+        } else {
+            &[]
+        };
+
+        // Create pipeline layout from bind groups, one per descriptor set
+        let pipeline_layout = self.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts,
+            push_constant_ranges,
+        });
+
+        // Create compute pipeline
+        self.device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: None,
+            layout: Some(&pipeline_layout),
+            module,
+            entry_point,
+        })
+    }
+
+    /// Creates a pipeline with one storage buffer binding per entry of `read_only`, in binding order,
+    /// followed by the `DispatchInfo` uniform.
+    pub(crate) fn create_storage_pipeline(&self, module: &wgpu::ShaderModule, read_only: &[bool]) -> (wgpu::BindGroupLayout, wgpu::ComputePipeline) {
+        let entries = storage_entries(read_only);
+        let bind_group_layout = self.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: None,
+            entries: &entries,
+        });
+        let compute_pipeline = self.create_pipeline(module, &[&bind_group_layout]);
+        (bind_group_layout, compute_pipeline)
+    }
+
+    pub(crate) fn create_bind_group(&self, bind_group_layout: &wgpu::BindGroupLayout, buffers: &[&wgpu::Buffer]) -> wgpu::BindGroup {
+        // Create bind group for GPU buffers, bound in order
+        let entries = buffers
+            .iter()
+            .enumerate()
+            .map(|(binding, buffer)| wgpu::BindGroupEntry {
+                binding: binding as u32,
+                resource: buffer.as_entire_binding(),
+            })
+            .collect::<Vec<_>>();
+        self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: bind_group_layout,
+            entries: &entries,
+        })
+    }
+
+    /// Computes the dispatch covering `len` elements and uploads the matching `DispatchInfo` uniform.
+    pub(crate) fn create_dispatch_info(&self, len: usize) -> Result<((u32, u32, u32), TrackedBuffer), ComputeError> {
+        self.create_dispatch_info_sized(len, WORKGROUP_SIZE)
+    }
+
+    pub(crate) fn create_dispatch_info_sized(
+        &self,
+        len: usize,
+        workgroup_size: u32,
+    ) -> Result<((u32, u32, u32), TrackedBuffer), ComputeError> {
+        self.create_dispatch_info_at(len, workgroup_size, 0)
+    }
+
+    /// Like `create_dispatch_info_sized`, for a dispatch whose first element is at `offset` in
+    /// the caller's whole input. Fails with `DispatchTooLarge` if the global indices wouldn't
+    /// fit the u32s in `DispatchInfo`.
+    pub(crate) fn create_dispatch_info_at(
+        &self,
+        len: usize,
+        workgroup_size: u32,
+        offset: usize,
+    ) -> Result<((u32, u32, u32), TrackedBuffer), ComputeError> {
+        let dims = dispatch_count(len, workgroup_size)?;
+        let end = offset as u64 + len as u64;
+        if end > u32::MAX as u64 {
+            return Err(ComputeError::DispatchTooLarge {
+                invocations: end,
+                max: u32::MAX as u64,
+            });
+        }
+        let info = DispatchInfo::with_offset(len as u32, dims.0 * workgroup_size, offset as u32);
+        let buffer = self.create_buffer_with_usage(&[info], wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST);
+        Ok((dims, buffer))
+    }
+
+    pub(crate) fn dispatch(&self, compute_pipeline: &wgpu::ComputePipeline, bind_groups: &[&wgpu::BindGroup], dims: (u32, u32, u32)) {
+        self.dispatch_labeled(compute_pipeline, bind_groups, dims, None)
+    }
+
+    /// Like `dispatch`, wrapping the pass in a debug group named `label` so GPU profilers and
+    /// capture tools show which kernel each dispatch belongs to.
+    pub(crate) fn dispatch_labeled(
+        &self,
+        compute_pipeline: &wgpu::ComputePipeline,
+        bind_groups: &[&wgpu::BindGroup],
+        dims: (u32, u32, u32),
+        label: Option<&str>,
+    ) {
+        self.dispatch_pass(compute_pipeline, bind_groups, dims, &PassDescriptor { label, timestamp_writes: None })
+    }
+
+    /// Like `dispatch`, recording the pass as described by `pass`.
+    pub(crate) fn dispatch_pass(
+        &self,
+        compute_pipeline: &wgpu::ComputePipeline,
+        bind_groups: &[&wgpu::BindGroup],
+        (x, y, z): (u32, u32, u32),
+        pass: &PassDescriptor<'_>,
+    ) {
+        // Create encoder for CPU - GPU communcation
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: pass.label });
+
+        // Begin compute dispatch
+        self.record_pass(&mut encoder, pass, |cpass| {
+            for (index, bind_group) in bind_groups.iter().enumerate() {
+                cpass.set_bind_group(index as u32, bind_group, &[]);
+            }
+            cpass.set_pipeline(compute_pipeline);
+            if pass.label.is_some() {
+                cpass.insert_debug_marker(&format!("dispatch {}x{}x{}", x, y, z));
+            }
+            cpass.dispatch(x, y, z);
+        });
+
+        self.queue.submit(Some(encoder.finish()));
+    }
+}
+
+impl Drop for GpuContext {
+    fn drop(&mut self) {
+        self.tracker.report_leaks();
+    }
+}
+
+// Try each backend set in priority order, returning the first adapter found
+async fn select_adapter(backends: &Backends) -> Result<wgpu::Adapter, ComputeError> {
+    for backend_bits in &backends.0 {
+        // Create wpgu instance
+        let instance = wgpu::Instance::new(*backend_bits);
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::HighPerformance,
+                compatible_surface: None,
+            })
+            .await;
+        if let Some(adapter) = adapter {
+            let info = adapter.get_info();
+            log::info!("Using {:?} adapter {} (requested {:?})", info.backend, info.name, backend_bits);
+            return Ok(adapter);
+        }
+    }
+    Err(ComputeError::AdapterNotFound)
+}
+
+// Enumerate each backend set's adapters in priority order, returning the most capable kind of
+// adapter with every feature and limit required
+fn select_capable_adapter(
+    backends: &Backends,
+    features: wgpu::Features,
+    limits: Option<&wgpu::Limits>,
+) -> Result<wgpu::Adapter, ComputeError> {
+    for backend_bits in &backends.0 {
+        let instance = wgpu::Instance::new(*backend_bits);
+        let adapter = instance
+            .enumerate_adapters(*backend_bits)
+            .filter(|adapter| {
+                let info = adapter.get_info();
+                let missing = features - adapter.features();
+                let satisfied = missing.is_empty() && limits.map_or(true, |limits| meets_limits(&adapter.limits(), limits));
+                if !satisfied {
+                    log::info!("Skipping {:?} adapter {}, it lacks required features or limits", info.backend, info.name);
+                }
+                satisfied
+            })
+            .min_by_key(|adapter| device_type_rank(adapter.get_info().device_type));
+        if let Some(adapter) = adapter {
+            let info = adapter.get_info();
+            log::info!("Using {:?} adapter {} (requested {:?})", info.backend, info.name, backend_bits);
+            return Ok(adapter);
+        }
+    }
+    Err(ComputeError::AdapterNotFound)
+}
+
+// Lower is preferred, mirroring PowerPreference::HighPerformance
+fn device_type_rank(device_type: wgpu::DeviceType) -> u32 {
+    match device_type {
+        wgpu::DeviceType::DiscreteGpu => 0,
+        wgpu::DeviceType::IntegratedGpu => 1,
+        wgpu::DeviceType::VirtualGpu => 2,
+        wgpu::DeviceType::Cpu => 3,
+        wgpu::DeviceType::Other => 4,
+    }
+}
+
+// Whether `available` is at least `required` in every limit compute kernels can run into
+fn meets_limits(available: &wgpu::Limits, required: &wgpu::Limits) -> bool {
+    available.max_bind_groups >= required.max_bind_groups
+        && available.max_dynamic_storage_buffers_per_pipeline_layout >= required.max_dynamic_storage_buffers_per_pipeline_layout
+        && available.max_dynamic_uniform_buffers_per_pipeline_layout >= required.max_dynamic_uniform_buffers_per_pipeline_layout
+        && available.max_storage_buffers_per_shader_stage >= required.max_storage_buffers_per_shader_stage
+        && available.max_uniform_buffers_per_shader_stage >= required.max_uniform_buffers_per_shader_stage
+        && available.max_storage_buffer_binding_size >= required.max_storage_buffer_binding_size
+        && available.max_uniform_buffer_binding_size >= required.max_uniform_buffer_binding_size
+        && available.max_push_constant_size >= required.max_push_constant_size
+}
+
+// Ask the surface's instance for an adapter that can present to it
+async fn select_surface_adapter(target: CompatibleSurface<'_>) -> Result<wgpu::Adapter, ComputeError> {
+    let adapter = target
+        .instance
+        .request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            compatible_surface: Some(target.surface),
+        })
+        .await
+        .ok_or(ComputeError::AdapterNotFound)?;
+    let info = adapter.get_info();
+    log::info!("Using {:?} adapter {} (compatible with surface)", info.backend, info.name);
+    Ok(adapter)
+}
+
+fn warn_uninitialized_workgroup_reads(label: Option<&str>, words: &[u32]) {
+    match uninitialized_workgroup_reads(bytemuck::cast_slice(words)) {
+        Ok(names) => {
+            for name in names {
+                log::warn!(
+                    "Shader {} may read workgroup memory {} before writing it",
+                    label.unwrap_or("<unnamed>"),
+                    name
+                );
+            }
+        }
+        Err(e) => log::warn!("Couldn't check shader {} for workgroup reads: {}", label.unwrap_or("<unnamed>"), e),
+    }
+}
+
+// Probe whether the device accepts a pipeline with an empty bind group layout
+async fn supports_empty_bindings(device: &wgpu::Device) -> bool {
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: None,
+        entries: &[],
+    });
+    let _ = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: None,
+        bind_group_layouts: &[&bind_group_layout],
+        push_constant_ranges: &[],
+    });
+    device.pop_error_scope().await.is_none()
+}
+
+// Probe whether the device accepts a storage buffer that is also mappable for reading
+async fn supports_map_direct(device: &wgpu::Device) -> bool {
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let _ = device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: wgpu::COPY_BUFFER_ALIGNMENT,
+        usage: MAP_DIRECT_USAGE,
+        mapped_at_creation: false,
+    });
+    device.pop_error_scope().await.is_none()
+}
+
+// Belt chunks are reused across uploads; larger writes get a dedicated chunk
+const BELT_CHUNK_SIZE: wgpu::BufferAddress = 4 << 20;
+
+pub(crate) const STORAGE_USAGE: wgpu::BufferUsage = wgpu::BufferUsage::from_bits_truncate(
+    wgpu::BufferUsage::STORAGE.bits() | wgpu::BufferUsage::COPY_DST.bits() | wgpu::BufferUsage::COPY_SRC.bits(),
+);
+
+// Storage buffers read back by mapping them, with MAPPABLE_PRIMARY_BUFFERS enabled
+pub(crate) const MAP_DIRECT_USAGE: wgpu::BufferUsage = wgpu::BufferUsage::from_bits_truncate(
+    wgpu::BufferUsage::STORAGE.bits() | wgpu::BufferUsage::COPY_DST.bits() | wgpu::BufferUsage::MAP_READ.bits(),
+);
+
+pub(crate) const READ_ONLY_USAGE: wgpu::BufferUsage =
+    wgpu::BufferUsage::from_bits_truncate(wgpu::BufferUsage::STORAGE.bits() | wgpu::BufferUsage::COPY_DST.bits());
+
+/// Bytes mapped to read the first `size` bytes of a buffer. Slices can't be empty, so a `size`
+/// of zero still maps the first copy-aligned word.
+fn mapped_size(size: wgpu::BufferAddress) -> wgpu::BufferAddress {
+    align::copy_size(size).max(wgpu::COPY_BUFFER_ALIGNMENT)
+}
+
+/// Copies `bytes` into a new vec of `T` with a single memcpy, skipping the zeroing a
+/// `vec![T::zeroed(); n]` would do first, which shows up on readbacks of hundreds of MB.
+/// Fails with `MisalignedInput` if `bytes.len()` isn't a multiple of `size_of::<T>()`.
+/// Zero-sized `T`s, like `()`, can't be counted from bytes, so only empty input is accepted
+/// for them.
+pub(crate) fn elements_from_bytes<T: Pod>(bytes: &[u8]) -> Result<Vec<T>, ComputeError> {
+    if size_of::<T>() == 0 {
+        if !bytes.is_empty() {
+            return Err(ComputeError::MisalignedInput {
+                len: bytes.len(),
+                elem_size: 0,
+            });
+        }
+        return Ok(Vec::new());
+    }
+    if bytes.len() % size_of::<T>() != 0 {
+        return Err(ComputeError::MisalignedInput {
+            len: bytes.len(),
+            elem_size: size_of::<T>(),
+        });
+    }
+    let len = bytes.len() / size_of::<T>();
+    let mut result = Vec::<T>::with_capacity(len);
+    // SAFETY: the allocation holds `len` elements, i.e. exactly `bytes.len()` bytes, and can't
+    // overlap `bytes`. A byte-wise copy has no alignment requirement on the source, and since
+    // `T: Pod` every bit pattern is a valid `T`, so all `len` elements are initialized after it
+    unsafe {
+        std::ptr::copy_nonoverlapping(bytes.as_ptr(), result.as_mut_ptr() as *mut u8, bytes.len());
+        result.set_len(len);
+    }
+    Ok(result)
+}
+
+/// Layout entries for storage buffers at bindings `0..read_only.len()`, then the `DispatchInfo` uniform.
+pub(crate) fn storage_entries(read_only: &[bool]) -> Vec<wgpu::BindGroupLayoutEntry> {
+    let mut entries = read_only
+        .iter()
+        .enumerate()
+        .map(|(binding, read_only)| storage_layout_entry(binding as u32, *read_only, None))
+        .collect::<Vec<_>>();
+    entries.push(uniform_layout_entry(read_only.len() as u32, size_of::<DispatchInfo>() as u64));
+    entries
+}
+
+pub(crate) fn uniform_layout_entry(binding: u32, size: u64) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        count: None,
+        visibility: wgpu::ShaderStage::COMPUTE,
+        ty: wgpu::BindingType::Buffer {
+            has_dynamic_offset: false,
+            min_binding_size: NonZeroU64::new(size),
+            ty: wgpu::BufferBindingType::Uniform,
+        },
+    }
+}
+
+pub(crate) fn storage_layout_entry(binding: u32, read_only: bool, count: Option<NonZeroU32>) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        count,
+        visibility: wgpu::ShaderStage::COMPUTE,
+        ty: wgpu::BindingType::Buffer {
+            has_dynamic_offset: false,
+            min_binding_size: Some(NonZeroU64::new(1).unwrap()),
+            ty: wgpu::BufferBindingType::Storage { read_only },
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn elements_from_bytes_round_trips() {
+        let values = [1.0f32, -2.5, 3.25];
+        let bytes = bytemuck::cast_slice::<f32, u8>(&values);
+        assert_eq!(elements_from_bytes::<f32>(bytes).unwrap(), values);
+        // Offset by one so the source isn't aligned for f32
+        let shifted = [&[0u8][..], bytes].concat();
+        assert_eq!(elements_from_bytes::<f32>(&shifted[1..]).unwrap(), values);
+    }
+
+    #[test]
+    fn elements_from_bytes_zero_sized() {
+        assert!(elements_from_bytes::<()>(&[]).unwrap().is_empty());
+        assert!(matches!(
+            elements_from_bytes::<()>(&[1, 2, 3]),
+            Err(ComputeError::MisalignedInput { len: 3, elem_size: 0 })
+        ));
+    }
+
+    #[test]
+    fn elements_from_bytes_rejects_partial_element() {
+        assert!(matches!(
+            elements_from_bytes::<f32>(&[0u8; 7]),
+            Err(ComputeError::MisalignedInput { len: 7, elem_size: 4 })
+        ));
+    }
+}
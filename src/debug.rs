@@ -0,0 +1,33 @@
+use bytemuck::Pod;
+
+use shared::WorkgroupDebug;
+
+use crate::{ComputeError, GpuContext};
+
+impl GpuContext {
+    /// Runs the kernel in place like `execute_kernel`, binding a zeroed `WorkgroupDebug` per
+    /// workgroup of the dispatch at binding 1, before the `DispatchInfo` uniform at binding 2.
+    /// Returns the output along with every record, in linear workgroup order, as a structured
+    /// stand-in for the printf debugging shaders lack.
+    pub async fn execute_kernel_debug<T: Pod>(
+        &self,
+        module: &wgpu::ShaderModule,
+        input: &[T],
+    ) -> Result<(Vec<T>, Vec<WorkgroupDebug>), ComputeError> {
+        if input.is_empty() {
+            return Ok((Vec::new(), Vec::new()));
+        }
+
+        let (bind_group_layout, compute_pipeline) = self.create_storage_pipeline(module, &[false, false]);
+        let storage_buffer = self.create_storage_buffer(input);
+        let (dims, info_buffer) = self.create_dispatch_info(input.len())?;
+        let workgroups = (dims.0 * dims.1 * dims.2) as usize;
+        let debug_buffer = self.create_output_buffer::<WorkgroupDebug>(workgroups);
+        let bind_group = self.create_bind_group(&bind_group_layout, &[&storage_buffer, &debug_buffer, &info_buffer]);
+
+        self.dispatch(&compute_pipeline, &[&bind_group], dims);
+        let output = self.read_buffer(&storage_buffer, input.len()).await?;
+        let records = self.read_buffer(&debug_buffer, workgroups).await?;
+        Ok((output, records))
+    }
+}
@@ -0,0 +1,103 @@
+use crate::ComputeError;
+
+/// Invocations per workgroup the bundled kernels are compiled with.
+pub const WORKGROUP_SIZE: u32 = 64;
+
+/// The per-dimension workgroup count every adapter is guaranteed to support.
+pub const MAX_WORKGROUPS_PER_DIMENSION: u32 = 65535;
+
+/// The invocations per workgroup every adapter is guaranteed to support. wgpu 0.9 neither
+/// exposes the real limit nor validates against it, so larger sizes aren't caught before dispatch.
+pub const MAX_WORKGROUP_SIZE: u32 = 256;
+
+/// Workgroup counts covering `total_threads` invocations. When more workgroups are needed than
+/// fit in X, the overflow is packed into Y, and shaders rebuild the linear index from
+/// `DispatchInfo`. For hand-written dispatches through `GpuContext::device`.
+///
+/// Fails with `ZeroWorkgroupSize` for a `workgroup_size` of zero, and with `DispatchTooLarge`
+/// when the workgroups don't fit in X and Y either.
+pub fn dispatch_count(total_threads: usize, workgroup_size: u32) -> Result<(u32, u32, u32), ComputeError> {
+    if workgroup_size == 0 {
+        return Err(ComputeError::ZeroWorkgroupSize);
+    }
+    let max_per_dimension = MAX_WORKGROUPS_PER_DIMENSION as u64;
+    // Rounded up without adding first, which could overflow for huge counts
+    let threads = total_threads as u64;
+    let workgroup_size = workgroup_size as u64;
+    let workgroups = threads / workgroup_size + (threads % workgroup_size != 0) as u64;
+    if workgroups <= max_per_dimension {
+        return Ok((workgroups as u32, 1, 1));
+    }
+    if workgroups > max_per_dimension * max_per_dimension {
+        return Err(ComputeError::DispatchTooLarge {
+            invocations: total_threads as u64,
+            max: max_per_dimension * max_per_dimension * workgroup_size,
+        });
+    }
+    let y = (workgroups + max_per_dimension - 1) / max_per_dimension;
+    let x = (workgroups + y - 1) / y;
+    Ok((x as u32, y as u32, 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAX: usize = MAX_WORKGROUPS_PER_DIMENSION as usize;
+
+    fn covered((x, y, z): (u32, u32, u32)) -> u64 {
+        x as u64 * y as u64 * z as u64 * WORKGROUP_SIZE as u64
+    }
+
+    #[test]
+    fn zero_threads() {
+        assert_eq!(dispatch_count(0, WORKGROUP_SIZE).unwrap(), (0, 1, 1));
+    }
+
+    #[test]
+    fn zero_workgroup_size() {
+        assert!(matches!(dispatch_count(64, 0), Err(ComputeError::ZeroWorkgroupSize)));
+    }
+
+    #[test]
+    fn multiples_of_workgroup_size() {
+        assert_eq!(dispatch_count(63, 64).unwrap(), (1, 1, 1));
+        assert_eq!(dispatch_count(64, 64).unwrap(), (1, 1, 1));
+        assert_eq!(dispatch_count(65, 64).unwrap(), (2, 1, 1));
+    }
+
+    #[test]
+    fn full_x_dimension() {
+        assert_eq!(dispatch_count(64 * MAX - 1, 64).unwrap(), (MAX as u32, 1, 1));
+        assert_eq!(dispatch_count(64 * MAX, 64).unwrap(), (MAX as u32, 1, 1));
+
+        // One more invocation spills into Y
+        let dims = dispatch_count(64 * MAX + 1, 64).unwrap();
+        assert_eq!(dims.1, 2);
+        assert!(dims.0 <= MAX_WORKGROUPS_PER_DIMENSION);
+        assert!(covered(dims) >= 64 * MAX as u64 + 1);
+    }
+
+    #[test]
+    fn full_x_and_y_dimensions() {
+        let limit = 64 * MAX * MAX;
+        assert_eq!(
+            dispatch_count(limit, 64).unwrap(),
+            (MAX_WORKGROUPS_PER_DIMENSION, MAX_WORKGROUPS_PER_DIMENSION, 1)
+        );
+        let dims = dispatch_count(limit - 1, 64).unwrap();
+        assert!(dims.0 <= MAX_WORKGROUPS_PER_DIMENSION && dims.1 <= MAX_WORKGROUPS_PER_DIMENSION);
+        assert!(covered(dims) >= limit as u64 - 1);
+
+        assert!(matches!(
+            dispatch_count(limit + 1, 64),
+            Err(ComputeError::DispatchTooLarge { .. })
+        ));
+    }
+
+    #[test]
+    fn over_the_limit() {
+        assert!(matches!(dispatch_count(usize::MAX, 64), Err(ComputeError::DispatchTooLarge { .. })));
+        assert!(matches!(dispatch_count(MAX * MAX + 1, 1), Err(ComputeError::DispatchTooLarge { .. })));
+    }
+}
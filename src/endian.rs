@@ -0,0 +1,115 @@
+use bytemuck::Pod;
+
+use shared::{
+    glam::{UVec4, Vec4},
+    Ray,
+};
+
+use crate::{ComputeError, GpuContext};
+
+/// Byte order to hand results out in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Endianness {
+    /// As the GPU wrote them, which matches the host.
+    Native,
+    Little,
+    Big,
+}
+
+impl Default for Endianness {
+    fn default() -> Self {
+        Endianness::Native
+    }
+}
+
+impl Endianness {
+    fn is_native(self) -> bool {
+        match self {
+            Endianness::Native => true,
+            Endianness::Little => cfg!(target_endian = "little"),
+            Endianness::Big => cfg!(target_endian = "big"),
+        }
+    }
+}
+
+/// Reverses the byte order of every field in place. Needed to convert structs, where swapping
+/// the whole element would also reorder the fields; implement it by swapping each field.
+pub trait ByteSwap {
+    fn swap_bytes(&mut self);
+}
+
+macro_rules! impl_byte_swap_int {
+    ($($ty:ty),*) => {
+        $(impl ByteSwap for $ty {
+            fn swap_bytes(&mut self) {
+                *self = <$ty>::swap_bytes(*self);
+            }
+        })*
+    };
+}
+
+impl_byte_swap_int!(u16, i16, u32, i32, u64, i64);
+
+impl ByteSwap for f32 {
+    fn swap_bytes(&mut self) {
+        *self = f32::from_bits(self.to_bits().swap_bytes());
+    }
+}
+
+impl ByteSwap for f64 {
+    fn swap_bytes(&mut self) {
+        *self = f64::from_bits(self.to_bits().swap_bytes());
+    }
+}
+
+impl<T: ByteSwap, const N: usize> ByteSwap for [T; N] {
+    fn swap_bytes(&mut self) {
+        self.iter_mut().for_each(ByteSwap::swap_bytes);
+    }
+}
+
+impl ByteSwap for Vec4 {
+    fn swap_bytes(&mut self) {
+        let mut components = self.to_array();
+        components.swap_bytes();
+        *self = Vec4::from(components);
+    }
+}
+
+impl ByteSwap for UVec4 {
+    fn swap_bytes(&mut self) {
+        let mut components = self.to_array();
+        components.swap_bytes();
+        *self = UVec4::from(components);
+    }
+}
+
+impl ByteSwap for Ray {
+    fn swap_bytes(&mut self) {
+        self.origin.swap_bytes();
+        self.direction.swap_bytes();
+    }
+}
+
+/// Converts `data` from native byte order to `endianness`.
+pub fn to_endianness<T: ByteSwap>(data: &mut [T], endianness: Endianness) {
+    if !endianness.is_native() {
+        data.iter_mut().for_each(ByteSwap::swap_bytes);
+    }
+}
+
+impl GpuContext {
+    /// Like `execute_kernel`, handing the output out in `endianness`, for feeding it to
+    /// network protocols or file formats with a fixed byte order. The results are no longer
+    /// meaningful as `T` on this host unless `endianness` matches it.
+    pub async fn execute_kernel_endian<T: Pod + ByteSwap>(
+        &self,
+        module: &wgpu::ShaderModule,
+        input: &[T],
+        endianness: Endianness,
+    ) -> Result<Vec<T>, ComputeError> {
+        let mut output = self.execute_kernel(module, input).await?;
+        to_endianness(&mut output, endianness);
+        Ok(output)
+    }
+}
@@ -0,0 +1,218 @@
+use std::fmt;
+
+/// Broad category of a `ComputeError`, for deciding how to react without matching every variant.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// No suitable adapter, or it lacks something the run needs. Try other backends or options.
+    Adapter,
+    /// The device couldn't be created.
+    Device,
+    /// The shader was rejected or doesn't fit the target.
+    Shader,
+    /// Input or buffer sizes don't fit the request or the device. Resize or chunk the input.
+    Buffer,
+    /// The run itself failed or produced bad output.
+    Runtime,
+    /// The request is inconsistent regardless of device, e.g. a pipeline over its stage limit.
+    Config,
+    /// Reading or writing a file failed.
+    Io,
+}
+
+#[derive(Debug)]
+pub enum ComputeError {
+    AdapterNotFound,
+    DeviceCreation(wgpu::RequestDeviceError),
+    UnsupportedFeatures(wgpu::Features),
+    MissingUsage(wgpu::BufferUsage),
+    UnsupportedShaderFeature(String),
+    InvalidSpirv(String),
+    PipelineCreation(String),
+    NonFiniteOutput { count: usize, first_index: usize },
+    LengthMismatch { expected: usize, actual: usize },
+    IndexOutOfRange { index: usize, len: usize },
+    MisalignedInput { len: usize, elem_size: usize },
+    UnalignedField { offset: u64, size: u64, stride: u64 },
+    UnalignedBytes { align: usize },
+    AliasingNotAllowed,
+    NoUsableWorkgroupSize,
+    Cancelled,
+    Io(std::io::Error),
+    IncompleteCoverage,
+    CapacityTooSmall { capacity: usize, len: usize },
+    BufferTooLarge { size: u64, max: u64 },
+    /// `group` is `None` when the number of bind groups itself is wrong.
+    BindingMismatch { group: Option<u32>, expected: usize, actual: usize },
+    ReadbackFailed(wgpu::BufferAsyncError),
+    PollTimeout { iterations: usize },
+    TooManyStages { max: usize },
+    StrideMismatch { type_name: &'static str, host_stride: usize, std430_stride: usize },
+    ZeroWorkgroupSize,
+    EmptyBindingArray,
+    NoColumns,
+    ReservedBinding { binding: u32 },
+    DispatchTooLarge { invocations: u64, max: u64 },
+}
+
+impl fmt::Display for ComputeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ComputeError::AdapterNotFound => write!(f, "failed to find an appropriate adapter"),
+            ComputeError::DeviceCreation(e) => write!(f, "failed to create device: {}", e),
+            ComputeError::UnsupportedFeatures(features) => {
+                write!(f, "adapter does not support required features: {:?}", features)
+            }
+            ComputeError::MissingUsage(usage) => write!(f, "buffer usage is missing required flags: {:?}", usage),
+            ComputeError::UnsupportedShaderFeature(reason) => write!(f, "shader is not supported by the target: {}", reason),
+            ComputeError::InvalidSpirv(reason) => write!(f, "invalid SPIR-V module: {}", reason),
+            ComputeError::PipelineCreation(reason) => write!(f, "failed to create compute pipeline: {}", reason),
+            ComputeError::NonFiniteOutput { count, first_index } => write!(
+                f,
+                "kernel produced {} non-finite values, the first at index {}",
+                count, first_index
+            ),
+            ComputeError::LengthMismatch { expected, actual } => {
+                write!(f, "expected {} elements but got {}", expected, actual)
+            }
+            ComputeError::IndexOutOfRange { index, len } => {
+                write!(f, "index {} is out of range for {} elements", index, len)
+            }
+            ComputeError::MisalignedInput { len, elem_size } => write!(
+                f,
+                "input of {} bytes is not a whole number of {}-byte elements",
+                len, elem_size
+            ),
+            ComputeError::UnalignedField { offset, size, stride } => write!(
+                f,
+                "field of {} bytes at offset {} must fit in the {}-byte stride and be copyable in 4-byte units",
+                size, offset, stride
+            ),
+            ComputeError::UnalignedBytes { align } => {
+                write!(f, "input bytes are not aligned to the element alignment of {}", align)
+            }
+            ComputeError::AliasingNotAllowed => write!(f, "buffer aliasing requires RunOptions::allow_aliasing"),
+            ComputeError::NoUsableWorkgroupSize => write!(f, "no workgroup size variant is supported by the adapter"),
+            ComputeError::Cancelled => write!(f, "kernel run was cancelled"),
+            ComputeError::Io(e) => write!(f, "i/o error: {}", e),
+            ComputeError::IncompleteCoverage => {
+                write!(f, "kernel did not write the last output element, check the dispatch size")
+            }
+            ComputeError::CapacityTooSmall { capacity, len } => write!(
+                f,
+                "buffer capacity of {} elements can't hold the {} input elements",
+                capacity, len
+            ),
+            ComputeError::BufferTooLarge { size, max } => write!(
+                f,
+                "buffer of {} bytes exceeds the device's storage binding limit of {}, use execute_kernel_streamed",
+                size, max
+            ),
+            ComputeError::BindingMismatch { group: Some(group), expected, actual } => write!(
+                f,
+                "shader declares {} bindings in group {} but {} buffers were given",
+                expected, group, actual
+            ),
+            ComputeError::BindingMismatch { group: None, expected, actual } => write!(
+                f,
+                "shader declares {} bind groups but {} were given",
+                expected, actual
+            ),
+            ComputeError::ReadbackFailed(e) => write!(f, "failed to map readback buffer: {}", e),
+            ComputeError::PollTimeout { iterations } => {
+                write!(f, "readback still pending after {} polls, the GPU may be hung", iterations)
+            }
+            ComputeError::TooManyStages { max } => write!(f, "pipeline already has the maximum of {} stages", max),
+            ComputeError::StrideMismatch { type_name, host_stride, std430_stride } => write!(
+                f,
+                "{} has a {}-byte stride on the host but {} bytes in a std430 array; \
+                 add padding fields until its size is a multiple of its alignment",
+                type_name, host_stride, std430_stride
+            ),
+            ComputeError::ZeroWorkgroupSize => write!(f, "workgroup size must be at least 1"),
+            ComputeError::EmptyBindingArray => write!(f, "binding array needs at least one buffer"),
+            ComputeError::NoColumns => write!(f, "can't split data into zero columns"),
+            ComputeError::ReservedBinding { binding } => write!(f, "binding {} is reserved by the crate's layout", binding),
+            ComputeError::DispatchTooLarge { invocations, max } => write!(
+                f,
+                "dispatch of {} invocations exceeds the limit of {}, split the input",
+                invocations, max
+            ),
+        }
+    }
+}
+
+impl ComputeError {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            ComputeError::AdapterNotFound | ComputeError::UnsupportedFeatures(_) | ComputeError::NoUsableWorkgroupSize => {
+                ErrorKind::Adapter
+            }
+            ComputeError::DeviceCreation(_) => ErrorKind::Device,
+            ComputeError::UnsupportedShaderFeature(_)
+            | ComputeError::InvalidSpirv(_)
+            | ComputeError::PipelineCreation(_)
+            | ComputeError::BindingMismatch { .. } => ErrorKind::Shader,
+            ComputeError::MissingUsage(_)
+            | ComputeError::LengthMismatch { .. }
+            | ComputeError::IndexOutOfRange { .. }
+            | ComputeError::MisalignedInput { .. }
+            | ComputeError::UnalignedField { .. }
+            | ComputeError::UnalignedBytes { .. }
+            | ComputeError::AliasingNotAllowed
+            | ComputeError::CapacityTooSmall { .. }
+            | ComputeError::BufferTooLarge { .. }
+            | ComputeError::StrideMismatch { .. }
+            | ComputeError::DispatchTooLarge { .. } => ErrorKind::Buffer,
+            ComputeError::NonFiniteOutput { .. }
+            | ComputeError::Cancelled
+            | ComputeError::IncompleteCoverage
+            | ComputeError::ReadbackFailed(_)
+            | ComputeError::PollTimeout { .. } => ErrorKind::Runtime,
+            ComputeError::TooManyStages { .. }
+            | ComputeError::ZeroWorkgroupSize
+            | ComputeError::EmptyBindingArray
+            | ComputeError::NoColumns
+            | ComputeError::ReservedBinding { .. } => ErrorKind::Config,
+            ComputeError::Io(_) => ErrorKind::Io,
+        }
+    }
+}
+
+impl std::error::Error for ComputeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ComputeError::DeviceCreation(e) => Some(e),
+            ComputeError::ReadbackFailed(e) => Some(e),
+            ComputeError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<wgpu::RequestDeviceError> for ComputeError {
+    fn from(e: wgpu::RequestDeviceError) -> Self {
+        ComputeError::DeviceCreation(e)
+    }
+}
+
+impl From<wgpu::BufferAsyncError> for ComputeError {
+    fn from(e: wgpu::BufferAsyncError) -> Self {
+        ComputeError::ReadbackFailed(e)
+    }
+}
+
+impl From<std::io::Error> for ComputeError {
+    fn from(e: std::io::Error) -> Self {
+        ComputeError::Io(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn too_many_stages_is_config() {
+        assert_eq!(ComputeError::TooManyStages { max: 4 }.kind(), ErrorKind::Config);
+    }
+}
@@ -0,0 +1,64 @@
+use bytemuck::Pod;
+
+use std::mem::size_of;
+
+use crate::{ComputeError, GpuContext};
+
+impl GpuContext {
+    /// Reads back one `F` field at byte `offset` from each of the first `len` elements of
+    /// `buffer`, which are `stride` bytes apart, copying just the field bytes into a compact
+    /// readback buffer. The origins of `Ray`s, for instance, are
+    /// `read_field::<Vec4>(buffer, len, 0, 32)`.
+    ///
+    /// Buffer copies work in 4-byte units, so `offset`, `stride` and the size of `F` must all
+    /// be multiples of `COPY_BUFFER_ALIGNMENT`.
+    pub async fn read_field<F: Pod>(
+        &self,
+        buffer: &wgpu::Buffer,
+        len: usize,
+        offset: u64,
+        stride: u64,
+    ) -> Result<Vec<F>, ComputeError> {
+        let size = size_of::<F>() as u64;
+        let alignment = wgpu::COPY_BUFFER_ALIGNMENT;
+        if offset % alignment != 0 || stride % alignment != 0 || size % alignment != 0 || offset + size > stride {
+            return Err(ComputeError::UnalignedField { offset, size, stride });
+        }
+        if len == 0 || size == 0 {
+            return Ok(vec![F::zeroed(); len]);
+        }
+
+        // One small copy per element, packed back to back
+        let readback_buffer = self.create_readback_buffer(len as u64 * size);
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        for index in 0..len as u64 {
+            encoder.copy_buffer_to_buffer(buffer, index * stride + offset, &readback_buffer, index * size, size);
+        }
+        self.queue.submit(Some(encoder.finish()));
+
+        self.map_read(&readback_buffer, len as u64 * size, |data| {
+            let mut result = vec![F::zeroed(); len];
+            bytemuck::cast_slice_mut(&mut result).copy_from_slice(data);
+            result
+        })
+        .await
+    }
+
+    /// Like `execute_kernel`, but only reads back the `F` field at byte `offset` of each
+    /// output element. See `read_field`.
+    pub async fn execute_kernel_field<T: Pod, F: Pod>(
+        &self,
+        module: &wgpu::ShaderModule,
+        input: &[T],
+        offset: u64,
+    ) -> Result<Vec<F>, ComputeError> {
+        let (bind_group_layout, compute_pipeline) = self.create_storage_pipeline(module, &[false]);
+        let storage_buffer = self.create_storage_buffer(input);
+        let (dims, info_buffer) = self.create_dispatch_info(input.len())?;
+        let bind_group = self.create_bind_group(&bind_group_layout, &[&storage_buffer, &info_buffer]);
+
+        self.dispatch(&compute_pipeline, &[&bind_group], dims);
+        self.read_field(&storage_buffer, input.len(), offset, size_of::<T>() as u64)
+            .await
+    }
+}
@@ -0,0 +1,47 @@
+use bytemuck::Pod;
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    mem::size_of,
+    path::Path,
+};
+
+use crate::{ComputeError, GpuContext};
+
+// Write the mapped range in pieces so the writer's buffer never has to hold all of it
+const WRITE_CHUNK_SIZE: usize = 1 << 20;
+
+impl GpuContext {
+    /// Like `execute_kernel`, but streams the result's bytes straight from the mapped readback
+    /// buffer into a file at `path` instead of collecting them into a `Vec`, for outputs too
+    /// large to hold in memory twice.
+    pub async fn execute_kernel_to_file<T: Pod>(
+        &self,
+        module: &wgpu::ShaderModule,
+        input: &[T],
+        path: &Path,
+    ) -> Result<(), ComputeError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        if input.is_empty() {
+            return Ok(writer.flush()?);
+        }
+
+        let (bind_group_layout, compute_pipeline) = self.create_storage_pipeline(module, &[false]);
+        let storage_buffer = self.create_storage_buffer(input);
+        let (dims, info_buffer) = self.create_dispatch_info(input.len())?;
+        let bind_group = self.create_bind_group(&bind_group_layout, &[&storage_buffer, &info_buffer]);
+        let size = (input.len() * size_of::<T>()) as wgpu::BufferAddress;
+
+        self.dispatch(&compute_pipeline, &[&bind_group], dims);
+        self.with_mapped(&storage_buffer, size, |data| {
+            data.chunks(WRITE_CHUNK_SIZE)
+                .try_for_each(|chunk| writer.write_all(chunk))
+        })
+        .await??;
+
+        // Flush explicitly so write errors aren't swallowed by drop
+        writer.flush()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,30 @@
+use crate::{ComputeError, GpuContext};
+
+/// Returns `ComputeError::NonFiniteOutput` if any of `values` is NaN or infinite.
+pub fn check_finite(values: &[f32]) -> Result<(), ComputeError> {
+    let mut non_finite = values.iter().enumerate().filter(|(_, v)| !v.is_finite());
+    if let Some((first_index, _)) = non_finite.next() {
+        return Err(ComputeError::NonFiniteOutput {
+            count: 1 + non_finite.count(),
+            first_index,
+        });
+    }
+    Ok(())
+}
+
+impl GpuContext {
+    /// Like `execute_kernel` for float kernels, optionally failing if the output contains
+    /// NaN or infinity instead of returning it silently.
+    pub async fn execute_kernel_f32(
+        &self,
+        module: &wgpu::ShaderModule,
+        input: &[f32],
+        check: bool,
+    ) -> Result<Vec<f32>, ComputeError> {
+        let result = self.execute_kernel(module, input).await?;
+        if check {
+            check_finite(&result)?;
+        }
+        Ok(result)
+    }
+}
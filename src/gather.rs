@@ -0,0 +1,79 @@
+use bytemuck::Pod;
+
+use std::mem::size_of;
+
+use crate::{align, ComputeError, GpuContext};
+
+impl GpuContext {
+    /// Like `execute_kernel`, but reads back only the elements at `indices`, in that order, for
+    /// sparse outputs where copying the whole buffer would be wasted bandwidth. Neighbouring
+    /// indices are coalesced into one copy.
+    pub async fn execute_kernel_gather<T: Pod>(
+        &self,
+        module: &wgpu::ShaderModule,
+        input: &[T],
+        indices: &[usize],
+    ) -> Result<Vec<T>, ComputeError> {
+        if let Some(&index) = indices.iter().find(|&&index| index >= input.len()) {
+            return Err(ComputeError::IndexOutOfRange { index, len: input.len() });
+        }
+        if indices.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let (bind_group_layout, compute_pipeline) = self.create_storage_pipeline(module, &[false]);
+        let storage_buffer = self.create_storage_buffer(input);
+        let (dims, info_buffer) = self.create_dispatch_info(input.len())?;
+        let bind_group = self.create_bind_group(&bind_group_layout, &[&storage_buffer, &info_buffer]);
+
+        // Byte ranges to copy, widened to COPY_BUFFER_ALIGNMENT and merged where they touch
+        let elem_size = size_of::<T>() as u64;
+        let buffer_size = align::copy_size(input.len() as u64 * elem_size);
+        let mut sorted = indices.to_vec();
+        sorted.sort_unstable();
+        let mut runs: Vec<(u64, u64)> = Vec::new();
+        for index in sorted {
+            let start = (index as u64 * elem_size) & !(wgpu::COPY_BUFFER_ALIGNMENT - 1);
+            let end = align::copy_size((index as u64 + 1) * elem_size).min(buffer_size);
+            match runs.last_mut() {
+                Some(run) if start <= run.1 => run.1 = run.1.max(end),
+                _ => runs.push((start, end)),
+            }
+        }
+
+        // Pack the runs back to back in the readback buffer
+        let total = runs.iter().map(|(start, end)| end - start).sum();
+        let readback_buffer = self.create_readback_buffer(total);
+        self.dispatch(&compute_pipeline, &[&bind_group], dims);
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        let mut packed_offset = 0;
+        for &(start, end) in &runs {
+            encoder.copy_buffer_to_buffer(&storage_buffer, start, &readback_buffer, packed_offset, end - start);
+            packed_offset += end - start;
+        }
+        self.queue.submit(Some(encoder.finish()));
+
+        self.map_read(&readback_buffer, total, |data| {
+            indices
+                .iter()
+                .map(|&index| {
+                    // Find the run holding the element and its position in the packed data
+                    let byte = index as u64 * elem_size;
+                    let mut packed = 0;
+                    for &(start, end) in &runs {
+                        if byte < end {
+                            packed += byte - start;
+                            break;
+                        }
+                        packed += end - start;
+                    }
+                    let mut element = T::zeroed();
+                    bytemuck::bytes_of_mut(&mut element)
+                        .copy_from_slice(&data[packed as usize..(packed + elem_size) as usize]);
+                    element
+                })
+                .collect()
+        })
+        .await
+    }
+}
@@ -0,0 +1,60 @@
+use bytemuck::Pod;
+use shared::DispatchInfo;
+
+use crate::{ComputeError, GpuContext, TrackedBuffer, MAX_WORKGROUPS_PER_DIMENSION, WORKGROUP_SIZE};
+
+impl GpuContext {
+    /// Runs a kernel that only produces output, such as noise or fractal generators, without
+    /// uploading an input first.
+    ///
+    /// The kernel gets a zeroed buffer of `output_len` `O`s at binding 0 and the `DispatchInfo`
+    /// for `output_len` elements at binding 1. Any parameters have to come from push constants
+    /// or be baked into the kernel.
+    ///
+    /// With `dims` of `None` the kernel is dispatched over `output_len` invocations, packed
+    /// into X and Y as by `dispatch_count`. `Some` dispatches exactly those workgroup counts
+    /// instead, e.g. one row of workgroups per image row, with `DispatchInfo::x_stride` set to
+    /// `dims.0 * WORKGROUP_SIZE`; the kernel then has to cover any elements the dispatch
+    /// doesn't reach itself, and skip invocations past `output_len`. Fails with
+    /// `DispatchTooLarge` if a dimension exceeds `MAX_WORKGROUPS_PER_DIMENSION`.
+    pub async fn generate_kernel<O: Pod>(
+        &self,
+        module: &wgpu::ShaderModule,
+        output_len: usize,
+        dims: Option<(u32, u32, u32)>,
+    ) -> Result<Vec<O>, ComputeError> {
+        if output_len == 0 {
+            return Ok(Vec::new());
+        }
+
+        let (bind_group_layout, compute_pipeline) = self.create_storage_pipeline(module, &[false]);
+        let output_buffer = self.create_output_buffer::<O>(output_len);
+        let (dims, info_buffer) = match dims {
+            None => self.create_dispatch_info(output_len)?,
+            Some(dims) => (dims, self.create_dispatch_info_for(output_len, dims)?),
+        };
+        let bind_group = self.create_bind_group(&bind_group_layout, &[&output_buffer, &info_buffer]);
+
+        self.dispatch(&compute_pipeline, &[&bind_group], dims);
+        self.read_buffer(&output_buffer, output_len).await
+    }
+
+    /// Uploads the `DispatchInfo` for `len` elements dispatched over the caller's `dims`.
+    fn create_dispatch_info_for(&self, len: usize, (x, y, z): (u32, u32, u32)) -> Result<TrackedBuffer, ComputeError> {
+        let largest = x.max(y).max(z);
+        if largest > MAX_WORKGROUPS_PER_DIMENSION {
+            return Err(ComputeError::DispatchTooLarge {
+                invocations: largest as u64 * WORKGROUP_SIZE as u64,
+                max: MAX_WORKGROUPS_PER_DIMENSION as u64 * WORKGROUP_SIZE as u64,
+            });
+        }
+        if len as u64 > u32::MAX as u64 {
+            return Err(ComputeError::DispatchTooLarge {
+                invocations: len as u64,
+                max: u32::MAX as u64,
+            });
+        }
+        let info = DispatchInfo::new(len as u32, x * WORKGROUP_SIZE);
+        Ok(self.create_buffer_with_usage(&[info], wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST))
+    }
+}
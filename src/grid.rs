@@ -0,0 +1,90 @@
+use bytemuck::Pod;
+
+use crate::{ComputeError, GpuContext};
+
+/// Row-major 2D view of a kernel's output, for image-style workloads.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Grid<T> {
+    data: Vec<T>,
+    width: usize,
+    height: usize,
+}
+
+impl<T> Grid<T> {
+    /// Wraps `data` as `height` rows of `width` elements, failing with
+    /// `ComputeError::LengthMismatch` if it doesn't hold exactly that many.
+    pub fn new(data: Vec<T>, width: usize, height: usize) -> Result<Self, ComputeError> {
+        if data.len() != width * height {
+            return Err(ComputeError::LengthMismatch {
+                expected: width * height,
+                actual: data.len(),
+            });
+        }
+        Ok(Self { data, width, height })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The element at column `x` of row `y`, or `None` outside the grid.
+    pub fn get(&self, x: usize, y: usize) -> Option<&T> {
+        if x < self.width && y < self.height {
+            self.data.get(y * self.width + x)
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut T> {
+        if x < self.width && y < self.height {
+            self.data.get_mut(y * self.width + x)
+        } else {
+            None
+        }
+    }
+
+    pub fn row(&self, y: usize) -> Option<&[T]> {
+        if y < self.height {
+            Some(&self.data[y * self.width..(y + 1) * self.width])
+        } else {
+            None
+        }
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        // chunks panics on zero, and a zero-width grid has no elements anyway
+        self.data.chunks(self.width.max(1))
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+
+    pub fn into_vec(self) -> Vec<T> {
+        self.data
+    }
+}
+
+impl GpuContext {
+    /// Like `execute_kernel`, for `input` laid out as `height` rows of `width` elements,
+    /// returning the output as a `Grid` of the same shape.
+    pub async fn execute_kernel_grid<T: Pod>(
+        &self,
+        module: &wgpu::ShaderModule,
+        input: &[T],
+        (width, height): (usize, usize),
+    ) -> Result<Grid<T>, ComputeError> {
+        if input.len() != width * height {
+            return Err(ComputeError::LengthMismatch {
+                expected: width * height,
+                actual: input.len(),
+            });
+        }
+        Grid::new(self.execute_kernel(module, input).await?, width, height)
+    }
+}
@@ -0,0 +1,136 @@
+use bytemuck::Pod;
+
+use std::{future::Future, mem::size_of};
+
+use crate::{align, ComputeError, GpuContext, TrackedBuffer};
+
+/// One in-place kernel run, recorded ahead of time so `GpuContext::submit_many` can batch it
+/// with others into a single submission.
+pub struct KernelJob {
+    compute_pipeline: wgpu::ComputePipeline,
+    bind_group: wgpu::BindGroup,
+    buffer: TrackedBuffer,
+    _info_buffer: TrackedBuffer,
+    dims: (u32, u32, u32),
+    size: wgpu::BufferAddress,
+    correlation_id: u64,
+}
+
+/// Output of a job run through `GpuContext::submit_kernel`, tagged with the job's correlation id.
+#[derive(Clone, Debug)]
+pub struct CompletedJob {
+    pub correlation_id: u64,
+    /// Raw output bytes, for the caller to cast back with `bytemuck`.
+    pub output: Vec<u8>,
+}
+
+impl KernelJob {
+    /// Tags the job with `id`, returned alongside its output by `submit_kernel` so callers with
+    /// many runs in flight can match results to requests whatever order they finish in.
+    pub fn with_correlation_id(mut self, id: u64) -> Self {
+        self.correlation_id = id;
+        self
+    }
+
+    pub fn correlation_id(&self) -> u64 {
+        self.correlation_id
+    }
+}
+
+impl GpuContext {
+    /// Uploads `input` and builds the pipeline for running `module` over it in place, like
+    /// `execute_kernel`, without dispatching anything yet.
+    pub fn kernel_job<T: Pod>(&self, module: &wgpu::ShaderModule, input: &[T]) -> Result<KernelJob, ComputeError> {
+        let (bind_group_layout, compute_pipeline) = self.create_storage_pipeline(module, &[false]);
+        let buffer = self.create_storage_buffer(input);
+        let (dims, info_buffer) = self.create_dispatch_info(input.len())?;
+        let bind_group = self.create_bind_group(&bind_group_layout, &[&buffer, &info_buffer]);
+        Ok(KernelJob {
+            compute_pipeline,
+            bind_group,
+            buffer,
+            _info_buffer: info_buffer,
+            dims,
+            size: (input.len() * size_of::<T>()) as wgpu::BufferAddress,
+            correlation_id: 0,
+        })
+    }
+
+    /// Submits `job` on its own right away and returns a future for its output. Unlike
+    /// `submit_many`, each job resolves independently, so with the background poller running
+    /// several can be awaited together (e.g. through `FuturesUnordered`) and complete out of
+    /// order; the correlation id says which request each result belongs to.
+    pub fn submit_kernel(&self, job: KernelJob) -> impl Future<Output = Result<CompletedJob, ComputeError>> + '_ {
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        {
+            let mut cpass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None });
+            cpass.set_pipeline(&job.compute_pipeline);
+            cpass.set_bind_group(0, &job.bind_group, &[]);
+            cpass.dispatch(job.dims.0, job.dims.1, job.dims.2);
+        }
+
+        // Zero-sized buffers can't be mapped
+        let copy_size = align::copy_size(job.size);
+        let readback_buffer = if copy_size > 0 {
+            let readback_buffer = self.create_readback_buffer(copy_size);
+            encoder.copy_buffer_to_buffer(&job.buffer, 0, &readback_buffer, 0, copy_size);
+            Some(readback_buffer)
+        } else {
+            None
+        };
+        self.queue.submit(Some(encoder.finish()));
+
+        async move {
+            let output = match &readback_buffer {
+                Some(readback_buffer) => self.map_read(readback_buffer, job.size, <[u8]>::to_vec).await?,
+                None => Vec::new(),
+            };
+            Ok(CompletedJob {
+                correlation_id: job.correlation_id,
+                output,
+            })
+        }
+    }
+
+    /// Records every job into one command encoder, each in its own pass, and submits them
+    /// together so the driver can overlap independent kernels. Returns each job's output as
+    /// raw bytes, in job order, for the caller to cast back with `bytemuck`.
+    pub async fn submit_many(&self, jobs: Vec<KernelJob>) -> Result<Vec<Vec<u8>>, ComputeError> {
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        let mut readback_buffers = Vec::with_capacity(jobs.len());
+        for job in &jobs {
+            {
+                let mut cpass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None });
+                cpass.set_pipeline(&job.compute_pipeline);
+                cpass.set_bind_group(0, &job.bind_group, &[]);
+                cpass.dispatch(job.dims.0, job.dims.1, job.dims.2);
+            }
+
+            // Zero-sized buffers can't be mapped
+            let copy_size = align::copy_size(job.size);
+            let readback_buffer = if copy_size > 0 {
+                let readback_buffer = self.create_readback_buffer(copy_size);
+                encoder.copy_buffer_to_buffer(&job.buffer, 0, &readback_buffer, 0, copy_size);
+                Some(readback_buffer)
+            } else {
+                None
+            };
+            readback_buffers.push(readback_buffer);
+        }
+        self.queue.submit(Some(encoder.finish()));
+
+        let mut results = Vec::with_capacity(jobs.len());
+        for (job, readback_buffer) in jobs.iter().zip(&readback_buffers) {
+            let readback_buffer = match readback_buffer {
+                Some(readback_buffer) => readback_buffer,
+                None => {
+                    results.push(Vec::new());
+                    continue;
+                }
+            };
+
+            results.push(self.map_read(readback_buffer, job.size, <[u8]>::to_vec).await?);
+        }
+        Ok(results)
+    }
+}
@@ -0,0 +1,69 @@
+use bytemuck::Pod;
+
+use crate::{context::storage_entries, ComputeError, GpuContext, WORKGROUP_SIZE};
+
+/// How a `ComputeKernel` lays out its storage buffers in descriptor set 0. Either way the
+/// `DispatchInfo` uniform follows the last storage buffer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KernelBindings {
+    /// One read-write buffer at binding 0 holding the input, overwritten with the output.
+    InPlace,
+    /// Read-only input at binding 0 and a separate output at binding 1.
+    InputOutput,
+}
+
+/// A single canonical definition of a kernel, so its shader, entry point, workgroup size and
+/// buffer layout can't drift apart between call sites.
+pub trait ComputeKernel {
+    type Input: Pod;
+    type Output: Pod;
+
+    const ENTRY_POINT: &'static str = "main_cs";
+    /// Must match the `threads(..)` the entry point was compiled with.
+    const WORKGROUP_SIZE: u32 = WORKGROUP_SIZE;
+    const BINDINGS: KernelBindings = KernelBindings::InputOutput;
+
+    fn shader() -> wgpu::ShaderModuleDescriptor<'static>;
+
+    /// Number of output elements produced for `input_len` input elements.
+    fn output_len(input_len: usize) -> usize {
+        input_len
+    }
+}
+
+impl GpuContext {
+    /// Runs kernel `K` over `input`, with one invocation per input element.
+    pub async fn run<K: ComputeKernel>(&self, input: &[K::Input]) -> Result<Vec<K::Output>, ComputeError> {
+        let module = self.try_create_shader_module(&K::shader()).await?;
+        let output_len = K::output_len(input.len());
+
+        let read_only: &[bool] = match K::BINDINGS {
+            KernelBindings::InPlace => &[false],
+            KernelBindings::InputOutput => &[true, false],
+        };
+        let bind_group_layout = self.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: None,
+            entries: &storage_entries(read_only),
+        });
+        let compute_pipeline = self
+            .try_create_pipeline_for_entry(&module, &[&bind_group_layout], K::ENTRY_POINT, 0)
+            .await?;
+
+        let (dims, info_buffer) = self.create_dispatch_info_sized(input.len(), K::WORKGROUP_SIZE)?;
+        match K::BINDINGS {
+            KernelBindings::InPlace => {
+                let input_buffer = self.create_storage_buffer(input);
+                let bind_group = self.create_bind_group(&bind_group_layout, &[&input_buffer, &info_buffer]);
+                self.dispatch(&compute_pipeline, &[&bind_group], dims);
+                self.read_buffer(&input_buffer, output_len).await
+            }
+            KernelBindings::InputOutput => {
+                let input_buffer = self.create_input_buffer(input);
+                let output_buffer = self.create_output_buffer::<K::Output>(output_len);
+                let bind_group = self.create_bind_group(&bind_group_layout, &[&input_buffer, &output_buffer, &info_buffer]);
+                self.dispatch(&compute_pipeline, &[&bind_group], dims);
+                self.read_buffer(&output_buffer, output_len).await
+            }
+        }
+    }
+}
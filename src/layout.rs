@@ -0,0 +1,195 @@
+use std::{
+    fmt,
+    mem::{align_of, size_of},
+};
+
+use shared::std430::Std430;
+
+use crate::{align::align_to, ComputeError, GpuContext, RunOptions, TrackedBuffer};
+
+/// How the crate sees a type's memory layout, for diagnosing structs that don't round-trip.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LayoutInfo {
+    pub type_name: &'static str,
+    pub size: usize,
+    pub align: usize,
+    /// Whether `size` is a multiple of 16, the stride std430 gives structs containing vectors
+    /// of three or four components.
+    pub is_16_byte_multiple: bool,
+}
+
+impl fmt::Display for LayoutInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: size {}, align {}, {}a multiple of 16 bytes",
+            self.type_name,
+            self.size,
+            self.align,
+            if self.is_16_byte_multiple { "" } else { "not " }
+        )
+    }
+}
+
+/// Layout of `T` as the crate uploads it, without touching the GPU.
+pub fn layout_info<T>() -> LayoutInfo {
+    LayoutInfo {
+        type_name: std::any::type_name::<T>(),
+        size: size_of::<T>(),
+        align: align_of::<T>(),
+        is_16_byte_multiple: size_of::<T>() % 16 == 0,
+    }
+}
+
+/// Checks the element type of a run against the std430 alignment in `options.element_align`,
+/// if one was given. Only the caller knows a type's members, so without it nothing is checked;
+/// size alone can't tell e.g. three `u32`s, which need padding to a `UVec3`'s 16-byte stride,
+/// from six `f32`s, which are fine at 24 bytes.
+///
+/// Logs a warning for a mismatch, or returns `ComputeError::StrideMismatch` if
+/// `options.strict_layout` is set.
+pub(crate) fn check_element_layout<T>(options: &RunOptions) -> Result<(), ComputeError> {
+    let align = match options.element_align {
+        Some(align) => align,
+        None => return Ok(()),
+    };
+    match check_stride::<T>(align) {
+        Err(e) if !options.strict_layout => {
+            log::warn!("{}", e);
+            Ok(())
+        }
+        result => result,
+    }
+}
+
+/// Checks that consecutive `T`s on the host are as far apart as in a std430 array, i.e. that
+/// `T`'s size is already a multiple of its largest member alignment. A struct of a `Vec3` and
+/// nothing else is 12 bytes on the host but strided by 16 in the shader, so every element after
+/// the first would be read from the wrong offset. Fails with `ComputeError::StrideMismatch`.
+///
+/// `execute_kernel_with` runs the same check before upload when `RunOptions::element_align` is
+/// set, see `RunOptions::std430`.
+pub fn check_std430_stride<T: Std430>() -> Result<(), ComputeError> {
+    check_stride::<T>(T::STD430_ALIGN)
+}
+
+/// Compares `T`'s host stride with its std430 array stride for a base alignment of `align`.
+fn check_stride<T>(align: usize) -> Result<(), ComputeError> {
+    let host_stride = size_of::<T>();
+    let std430_stride = align_to(host_stride as u64, align as u64) as usize;
+    if host_stride != std430_stride {
+        return Err(ComputeError::StrideMismatch {
+            type_name: std::any::type_name::<T>(),
+            host_stride,
+            std430_stride,
+        });
+    }
+    Ok(())
+}
+
+impl GpuContext {
+    /// Like `create_storage_buffer`, first checking `T` with `check_std430_stride`.
+    pub fn create_storage_buffer_checked<T: bytemuck::Pod + Std430>(&self, contents: &[T]) -> Result<TrackedBuffer, ComputeError> {
+        check_std430_stride::<T>()?;
+        Ok(self.create_storage_buffer(contents))
+    }
+}
+
+/// Reinterprets untrusted `bytes`, e.g. from FFI or a file, as a slice of `T`, failing with
+/// `ComputeError::MisalignedInput` if the length isn't a whole number of elements and
+/// `ComputeError::UnalignedBytes` if the data isn't aligned for `T`. Copy into a `Vec<T>`
+/// instead when the source alignment can't be controlled.
+pub fn checked_cast<T: bytemuck::Pod>(bytes: &[u8]) -> Result<&[T], ComputeError> {
+    bytemuck::try_cast_slice(bytes).map_err(|e| match e {
+        bytemuck::PodCastError::TargetAlignmentGreaterAndInputNotAligned => ComputeError::UnalignedBytes {
+            align: align_of::<T>(),
+        },
+        _ => ComputeError::MisalignedInput {
+            len: bytes.len(),
+            elem_size: size_of::<T>(),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Backed by u32s so the start of the byte view is 4-byte aligned
+    fn aligned_bytes(words: &[u32]) -> &[u8] {
+        bytemuck::cast_slice(words)
+    }
+
+    #[test]
+    fn checked_cast_accepts_aligned_whole_elements() {
+        let words = [1u32, 2, 3];
+        assert_eq!(checked_cast::<u32>(aligned_bytes(&words)).unwrap(), words);
+    }
+
+    #[test]
+    fn checked_cast_rejects_odd_offset() {
+        let words = [0u32; 3];
+        assert!(matches!(
+            checked_cast::<u32>(&aligned_bytes(&words)[1..5]),
+            Err(ComputeError::UnalignedBytes { align: 4 })
+        ));
+    }
+
+    #[test]
+    fn checked_cast_rejects_partial_element() {
+        let words = [0u32; 3];
+        assert!(matches!(
+            checked_cast::<u32>(&aligned_bytes(&words)[..6]),
+            Err(ComputeError::MisalignedInput { len: 6, elem_size: 4 })
+        ));
+    }
+
+    fn strict(element_align: Option<usize>) -> RunOptions {
+        RunOptions {
+            element_align,
+            strict_layout: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn unchecked_without_element_align() {
+        assert!(check_element_layout::<[u32; 3]>(&strict(None)).is_ok());
+    }
+
+    #[test]
+    fn six_floats_pass() {
+        // 24 bytes isn't a multiple of 16, but scalars only need 4-byte alignment
+        assert!(check_element_layout::<[f32; 6]>(&strict(Some(4))).is_ok());
+    }
+
+    #[test]
+    fn unpadded_vector_struct_fails() {
+        assert!(matches!(
+            check_element_layout::<[u32; 3]>(&strict(Some(16))),
+            Err(ComputeError::StrideMismatch { host_stride: 12, std430_stride: 16, .. })
+        ));
+        // Only a warning unless strict
+        let options = RunOptions {
+            element_align: Some(16),
+            ..Default::default()
+        };
+        assert!(check_element_layout::<[u32; 3]>(&options).is_ok());
+    }
+
+    #[test]
+    fn std430_stride_of_payloads() {
+        assert!(check_std430_stride::<shared::Ray>().is_ok());
+        assert!(check_std430_stride::<u32>().is_ok());
+        assert!(matches!(
+            check_std430_stride::<shared::glam::Vec3>(),
+            Err(ComputeError::StrideMismatch { host_stride: 12, std430_stride: 16, .. })
+        ));
+    }
+
+    #[test]
+    fn std430_options_match_check_std430_stride() {
+        assert!(check_element_layout::<shared::Ray>(&RunOptions::std430::<shared::Ray>()).is_ok());
+        assert!(check_element_layout::<shared::glam::Vec3>(&RunOptions::std430::<shared::glam::Vec3>()).is_err());
+    }
+}
@@ -0,0 +1,87 @@
+mod accumulate;
+mod adaptive;
+pub mod align;
+mod batch;
+mod capture;
+mod chunked;
+mod completion;
+mod context;
+mod debug;
+mod dispatch;
+mod endian;
+mod error;
+mod field;
+mod file;
+mod finite;
+mod gather;
+mod generate;
+mod grid;
+mod job;
+mod kernel;
+mod layout;
+mod mapped;
+mod options;
+mod pad;
+#[cfg(feature = "rayon")]
+mod par;
+mod ping_pong;
+mod pipeline;
+mod poller;
+mod pool;
+mod predicate;
+mod progress;
+mod reflect;
+mod resources;
+mod scan;
+mod scene;
+mod seed;
+mod smoke;
+pub mod soa;
+mod spirv;
+mod staging;
+mod stateful;
+mod stats;
+mod sweep;
+mod tail;
+mod timing;
+mod tracker;
+mod transform;
+mod validate;
+
+pub use adaptive::WorkgroupVariant;
+pub use batch::BatchResults;
+pub use capture::replay;
+pub use chunked::CancellationToken;
+pub use completion::CompletionFuture;
+pub use context::GpuContext;
+pub use dispatch::{dispatch_count, MAX_WORKGROUPS_PER_DIMENSION, MAX_WORKGROUP_SIZE, WORKGROUP_SIZE};
+pub use endian::{to_endianness, ByteSwap, Endianness};
+pub use error::{ComputeError, ErrorKind};
+pub use finite::check_finite;
+pub use grid::Grid;
+pub use job::{CompletedJob, KernelJob};
+pub use kernel::{ComputeKernel, KernelBindings};
+pub use layout::{check_std430_stride, checked_cast, layout_info, LayoutInfo};
+pub use mapped::{MappedIter, MappedOutput};
+pub use options::{Backends, CompatibleSurface, ComputeOptions, PollMode, ReadbackStrategy, RunOptions};
+pub use ping_pong::Convergence;
+pub use pipeline::{Pipeline, PipelineOutput, DEFAULT_MAX_STAGES};
+pub use pool::{BufferPool, PoolStats, PooledBuffer};
+pub use reflect::{reflect_bindings, BindingKind, ReflectedBinding};
+pub use resources::{NamedBuffer, ResourceSet};
+pub use scene::SceneBinding;
+pub use seed::seed_for;
+pub use spirv::{shader_descriptor, spirv_words};
+pub use stateful::StatefulKernel;
+pub use stats::RunStats;
+pub use sweep::PreparedInput;
+pub use timing::{PassDescriptor, TimestampWrites};
+pub use tracker::TrackedBuffer;
+pub use transform::std140_mat4_array;
+pub use validate::{check_deterministic, uninitialized_workgroup_reads, validate_spirv, CapabilityProfile};
+
+pub async fn execute_kernel<T: bytemuck::Pod>(shader_binary: wgpu::ShaderModuleDescriptor<'_>, input: &[T]) -> Result<Vec<T>, ComputeError> {
+    let context = GpuContext::new().await?;
+    let module = context.try_create_shader_module(&shader_binary).await?;
+    context.execute_kernel(&module, input).await
+}
@@ -0,0 +1,144 @@
+use bytemuck::Pod;
+
+use std::{marker::PhantomData, mem::size_of};
+
+use crate::{align, ComputeError, GpuContext, TrackedBuffer};
+
+/// A kernel's output left in a mapped readback buffer, decoded element by element on demand
+/// instead of copied into a `Vec` up front. The buffer is unmapped when this is dropped.
+pub struct MappedOutput<'a, T: Pod> {
+    context: &'a GpuContext,
+    // The one view of the mapped range, shared by every iterator, since wgpu panics on a
+    // second overlapping one. It borrows from `buffer`, see `execute_kernel_mapped`
+    view: Option<wgpu::BufferView<'static>>,
+    buffer: Option<Box<TrackedBuffer>>,
+    len: usize,
+    _marker: PhantomData<T>,
+}
+
+/// Iterator over the elements of a `MappedOutput`, reading each from the mapped bytes.
+pub struct MappedIter<'b, T: Pod> {
+    bytes: &'b [u8],
+    index: usize,
+    len: usize,
+    _marker: PhantomData<T>,
+}
+
+impl GpuContext {
+    /// Like `execute_kernel`, but returns the output still mapped, for consumers that handle
+    /// one element at a time and may stop early.
+    pub async fn execute_kernel_mapped<T: Pod>(
+        &self,
+        module: &wgpu::ShaderModule,
+        input: &[T],
+    ) -> Result<MappedOutput<'_, T>, ComputeError> {
+        let mut output = MappedOutput {
+            context: self,
+            view: None,
+            buffer: None,
+            len: input.len(),
+            _marker: PhantomData,
+        };
+        if input.is_empty() {
+            return Ok(output);
+        }
+
+        let (bind_group_layout, compute_pipeline) = self.create_storage_pipeline(module, &[false]);
+        let storage_buffer = self.create_storage_buffer(input);
+        let (dims, info_buffer) = self.create_dispatch_info(input.len())?;
+        let bind_group = self.create_bind_group(&bind_group_layout, &[&storage_buffer, &info_buffer]);
+        self.dispatch(&compute_pipeline, &[&bind_group], dims);
+
+        let copy_size = align::copy_size((input.len() * size_of::<T>()) as u64);
+        let readback_buffer = self.create_readback_buffer(copy_size);
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        encoder.copy_buffer_to_buffer(&storage_buffer, 0, &readback_buffer, 0, copy_size);
+        self.queue.submit(Some(encoder.finish()));
+
+        self.wait_for(readback_buffer.slice(..).map_async(wgpu::MapMode::Read)).await?;
+        self.tracker.mapped();
+
+        // SAFETY: the view borrows the boxed buffer, which stays at the same address however
+        // the output moves, and `Drop` releases the view before unmapping and freeing it
+        let readback_buffer = Box::new(readback_buffer);
+        let view = readback_buffer.slice(..).get_mapped_range();
+        output.view = Some(unsafe { std::mem::transmute::<wgpu::BufferView<'_>, wgpu::BufferView<'static>>(view) });
+        output.buffer = Some(readback_buffer);
+        Ok(output)
+    }
+}
+
+impl<'a, T: Pod> MappedOutput<'a, T> {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn iter(&self) -> MappedIter<'_, T> {
+        MappedIter {
+            bytes: self.view.as_deref().unwrap_or(&[]),
+            index: 0,
+            len: self.len,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, T: Pod> Drop for MappedOutput<'a, T> {
+    fn drop(&mut self) {
+        self.view = None;
+        if let Some(buffer) = &self.buffer {
+            buffer.unmap();
+            self.context.tracker.unmapped();
+        }
+    }
+}
+
+impl<'b, T: Pod> Iterator for MappedIter<'b, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.index >= self.len {
+            return None;
+        }
+
+        // Copy out since the mapped bytes needn't be aligned for T
+        let start = self.index * size_of::<T>();
+        let mut element = T::zeroed();
+        bytemuck::bytes_of_mut(&mut element).copy_from_slice(&self.bytes[start..start + size_of::<T>()]);
+        self.index += 1;
+        Some(element)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.len - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'b, T: Pod> ExactSizeIterator for MappedIter<'b, T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn iterators_share_the_mapping() {
+        let context = futures::executor::block_on(GpuContext::new()).unwrap();
+        let shader = crate::shader_descriptor(include_bytes!(env!("compute.spv"))).unwrap();
+        let module = context.create_shader_module(&shader);
+        let input = (0..1000u32).collect::<Vec<_>>();
+
+        let output = futures::executor::block_on(context.execute_kernel_mapped(&module, &input)).unwrap();
+        let first = output.iter();
+        let mut second = output.iter();
+        second.next();
+        assert_eq!(first.len(), input.len());
+        assert_eq!(second.len(), input.len() - 1);
+        assert!(first.skip(1).eq(second));
+    }
+}
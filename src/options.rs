@@ -0,0 +1,205 @@
+use shared::std430::Std430;
+
+/// Backend sets tried in order during adapter selection, stopping at the first that yields an adapter.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Backends(pub Vec<wgpu::BackendBit>);
+
+impl Backends {
+    /// Vulkan first, then the other primary backends, then whatever else is available.
+    pub fn prefer_vulkan() -> Self {
+        Self(vec![
+            wgpu::BackendBit::VULKAN,
+            wgpu::BackendBit::PRIMARY,
+            wgpu::BackendBit::SECONDARY,
+        ])
+    }
+
+    /// Primary backends first, falling back to secondary ones like GL where they're the only option.
+    pub fn all() -> Self {
+        Self(vec![wgpu::BackendBit::PRIMARY, wgpu::BackendBit::SECONDARY])
+    }
+
+    pub fn web() -> Self {
+        Self(vec![wgpu::BackendBit::BROWSER_WEBGPU, wgpu::BackendBit::GL])
+    }
+}
+
+impl Default for Backends {
+    fn default() -> Self {
+        Self(vec![wgpu::BackendBit::PRIMARY])
+    }
+}
+
+/// How a context waits for readback buffers to map when no background poller is running.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PollMode {
+    /// Block the calling thread in `Maintain::Wait` until the GPU is done.
+    Wait,
+    /// Call `Maintain::Poll` in a loop, yielding to the executor between polls so other tasks
+    /// keep running. Use this inside async runtimes.
+    Poll,
+}
+
+impl Default for PollMode {
+    fn default() -> Self {
+        PollMode::Wait
+    }
+}
+
+/// How `execute_kernel_with` gets results back to the CPU.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReadbackStrategy {
+    /// Map the storage buffer directly on integrated GPUs, where it lives in memory the CPU
+    /// can see anyway, and copy through a readback buffer everywhere else.
+    Auto,
+    /// Always copy into a separate `MAP_READ` buffer, which every adapter supports.
+    Copy,
+    /// Always map the storage buffer directly. Needs `Features::MAPPABLE_PRIMARY_BUFFERS`.
+    ///
+    /// Like `Auto`, falls back to copying if the device rejects a test `MAP_READ | STORAGE`
+    /// buffer at context creation; check `GpuContext::uses_direct_mapping` for the outcome.
+    MapDirect,
+}
+
+impl Default for ReadbackStrategy {
+    fn default() -> Self {
+        ReadbackStrategy::Auto
+    }
+}
+
+/// A window surface the compute adapter must be able to present to, along with the instance
+/// it was created from.
+#[derive(Clone, Copy)]
+pub struct CompatibleSurface<'a> {
+    pub instance: &'a wgpu::Instance,
+    pub surface: &'a wgpu::Surface,
+}
+
+impl std::fmt::Debug for CompatibleSurface<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CompatibleSurface").finish()
+    }
+}
+
+/// Settings used when creating a `GpuContext`.
+#[derive(Clone, Debug, Default)]
+pub struct ComputeOptions<'a> {
+    pub backends: Backends,
+    /// Picks the adapter driving this surface, so an app that also renders to a window runs
+    /// compute on the same GPU. `backends` is ignored when this is set, since the adapter has
+    /// to come from the surface's own instance. Leave it `None` for headless compute.
+    pub compatible_surface: Option<CompatibleSurface<'a>>,
+    /// Features the device must be created with.
+    pub features: wgpu::Features,
+    /// Limits the device must be created with, in place of the defaults. When this is set or
+    /// `features` isn't empty, adapter selection enumerates every adapter of each backend set
+    /// and picks the most capable kind, discrete GPUs first, among those meeting both, so a
+    /// capable adapter isn't passed over for one that can't run the kernel.
+    pub required_limits: Option<wgpu::Limits>,
+    /// Use genuinely empty bind group layouts for kernels without buffers when the adapter
+    /// accepts them, instead of always binding a dummy buffer.
+    pub allow_empty_bindings: bool,
+    pub poll_mode: PollMode,
+    /// Most `Maintain::Poll` calls made for one readback in `PollMode::Poll` before giving up
+    /// with `ComputeError::PollTimeout`, so a hung GPU doesn't spin the loop forever. 0 means
+    /// no limit. Has no effect in `PollMode::Wait` or with the background poller running.
+    pub max_poll_iters: usize,
+    pub readback: ReadbackStrategy,
+    /// Validates every shader module with naga when it is created, so malformed kernels fail
+    /// there instead of misbehaving at dispatch.
+    ///
+    /// wgpu 0.9 has no switch for runtime bounds checks. Out-of-bounds accesses are already
+    /// clamped or zeroed on Vulkan, where wgpu enables `robustBufferAccess`, and on the other
+    /// backends depend on the driver. Kernels should still bounds-check against `DispatchInfo`.
+    pub debug_checks: bool,
+    /// Makes `try_create_shader_module` reject SPIR-V that `check_deterministic` flags with
+    /// `ComputeError::UnsupportedShaderFeature`, for users who need bit-identical results.
+    /// Only the module's own fast-math opt-ins are caught; the README lists what each backend
+    /// may still do on its own.
+    pub deterministic: bool,
+}
+
+/// Per-run settings for `GpuContext::execute_kernel_with`.
+#[derive(Clone, Debug)]
+pub struct RunOptions {
+    /// Number of discarded dispatches to run before the measured one, so lazy pipeline
+    /// compilation in the driver doesn't skew timings.
+    pub warmup: usize,
+    /// Replaces the default usage of the storage buffer, which depends on the readback path
+    /// chosen by `ComputeOptions::readback`: `STORAGE | COPY_DST | COPY_SRC` when copying
+    /// through a readback buffer, and `STORAGE | COPY_DST | MAP_READ` when mapping it directly.
+    ///
+    /// The override must keep `STORAGE` and whichever of `COPY_SRC` or `MAP_READ` that path
+    /// reads back through; otherwise `execute_kernel_with_stats` fails with
+    /// `ComputeError::MissingUsage` naming the missing flags.
+    pub usage_override: Option<wgpu::BufferUsage>,
+    /// Permits `execute_kernel_aliased` to bind one buffer at several bindings.
+    pub allow_aliasing: bool,
+    /// Seeds the last output element with a sentinel and fails with
+    /// `ComputeError::IncompleteCoverage` if the kernel leaves it untouched.
+    pub verify_coverage: bool,
+    /// std430 alignment of the element type, i.e. that of its most aligned member, e.g.
+    /// `Ray::STD430_ALIGN` from `shared::std430::Std430`. When set, a host stride that differs
+    /// from the std430 array stride is reported before upload; unset, it isn't checked.
+    pub element_align: Option<usize>,
+    /// Fail with `ComputeError::StrideMismatch` instead of warning about `element_align`.
+    pub strict_layout: bool,
+    /// Most submissions `execute_kernel_batch` leaves outstanding before waiting for the
+    /// oldest, bounding the memory held by pending readbacks. Zero means no limit.
+    pub max_in_flight: usize,
+    /// How many times `execute_kernel_batch_retrying` re-submits inputs that failed with an
+    /// `ErrorKind::Runtime` error before reporting them as failed.
+    pub retries: usize,
+    /// Names the command encoder and compute pass, and wraps the dispatch in a debug group,
+    /// so runs are easy to find in tools like RenderDoc.
+    pub label: Option<String>,
+    /// Elements to allocate, and read back, for the storage buffer of `execute_kernel_with`,
+    /// when the kernel appends past the end of its input. Only the input is uploaded, and the
+    /// dispatch still covers one invocation per input element.
+    pub buffer_capacity: Option<usize>,
+    /// Binds one u32 seed per element, derived from this seed with `seed_for`, as a read-only
+    /// storage buffer at binding 1 for stochastic kernels. `DispatchInfo` moves to binding 2.
+    pub seed: Option<u64>,
+    /// Whether `execute_kernel_inout_with` reads back binding 0 as well as the output. Turn it
+    /// off when the kernel doesn't modify its input, to save the copy. Defaults to true.
+    pub readback_input: bool,
+}
+
+impl Default for RunOptions {
+    fn default() -> Self {
+        Self {
+            warmup: 0,
+            usage_override: None,
+            allow_aliasing: false,
+            verify_coverage: false,
+            element_align: None,
+            strict_layout: false,
+            max_in_flight: 0,
+            retries: 0,
+            label: None,
+            buffer_capacity: None,
+            seed: None,
+            readback_input: true,
+        }
+    }
+}
+
+impl RunOptions {
+    /// Default options that check `T` with `check_std430_stride` before upload, failing on a
+    /// mismatch.
+    pub fn std430<T: Std430>() -> Self {
+        Self {
+            element_align: Some(T::STD430_ALIGN),
+            strict_layout: true,
+            ..Default::default()
+        }
+    }
+
+    /// Default options with per-invocation seeds derived from `seed`.
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            seed: Some(seed),
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use bytemuck::Pod;
+
+use crate::{dispatch::{dispatch_count, WORKGROUP_SIZE}, ComputeError, GpuContext};
+
+impl GpuContext {
+    /// Like `execute_kernel`, but with `pad_value` set, pads the input with copies of it up to
+    /// the number of invocations its dispatch launches, so every invocation has an element and
+    /// kernels needn't bounds-check. Past `MAX_WORKGROUPS_PER_DIMENSION` workgroups the dispatch
+    /// spills into Y and rounds up to whole rows, so that is more than the next multiple of the
+    /// workgroup size. The kernel must handle the padding harmlessly, for instance by skipping
+    /// the sentinel. Padding is trimmed from the result.
+    pub async fn execute_kernel_padded<T: Pod>(
+        &self,
+        module: &wgpu::ShaderModule,
+        input: &[T],
+        pad_value: Option<T>,
+    ) -> Result<Vec<T>, ComputeError> {
+        let pad_value = match pad_value {
+            Some(pad_value) => pad_value,
+            None => return self.execute_kernel(module, input).await,
+        };
+
+        // Padding the input to exactly what its dispatch covers gives the padded input the
+        // same dispatch, so no invocation goes without an element
+        let (x, y, z) = dispatch_count(input.len(), WORKGROUP_SIZE)?;
+        let padded_len = x as usize * y as usize * z as usize * WORKGROUP_SIZE as usize;
+        let mut padded = Vec::with_capacity(padded_len);
+        padded.extend_from_slice(input);
+        padded.resize(padded_len, pad_value);
+
+        let mut result = self.execute_kernel(module, &padded).await?;
+        result.truncate(input.len());
+        Ok(result)
+    }
+}
@@ -0,0 +1,27 @@
+use bytemuck::Pod;
+
+use rayon::prelude::*;
+
+use crate::{ComputeError, GpuContext};
+
+impl GpuContext {
+    /// Runs the kernel like `execute_kernel` and maps `f` over its output on rayon's thread pool,
+    /// for workloads where CPU post-processing costs as much as the kernel itself.
+    ///
+    /// Outputs are plain `Vec`s, so `par_iter` works on them directly; this just saves the
+    /// boilerplate. Preserves element order.
+    pub async fn execute_kernel_par_map<T, R, F>(
+        &self,
+        module: &wgpu::ShaderModule,
+        input: &[T],
+        f: F,
+    ) -> Result<Vec<R>, ComputeError>
+    where
+        T: Pod + Send,
+        R: Send,
+        F: Fn(T) -> R + Sync + Send,
+    {
+        let output = self.execute_kernel(module, input).await?;
+        Ok(output.into_par_iter().map(f).collect())
+    }
+}
@@ -0,0 +1,139 @@
+use bytemuck::Pod;
+
+use std::mem::size_of;
+
+use shared::DispatchInfo;
+
+use crate::{
+    context::{storage_layout_entry, uniform_layout_entry},
+    ComputeError, GpuContext,
+};
+
+/// Result of `GpuContext::run_until`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Convergence<T> {
+    pub state: Vec<T>,
+    /// Steps that were run, including the one that converged.
+    pub iterations: usize,
+    /// False if `max_iters` ran out first.
+    pub converged: bool,
+}
+
+impl GpuContext {
+    /// Runs the kernel `iterations` times over two storage buffers that swap roles each step,
+    /// for stencil and simulation kernels that must not read cells the same step overwrote.
+    ///
+    /// The kernel reads the current state at binding 0 and writes the next state to binding 1,
+    /// with `DispatchInfo` at binding 2. State stays on the GPU until the final readback.
+    pub async fn ping_pong<T: Pod>(
+        &self,
+        module: &wgpu::ShaderModule,
+        initial: &[T],
+        iterations: usize,
+    ) -> Result<Vec<T>, ComputeError> {
+        // Nothing to step, and zero-sized buffers can't be bound
+        if iterations == 0 || initial.is_empty() {
+            return Ok(initial.to_vec());
+        }
+
+        let (bind_group_layout, compute_pipeline) = self.create_storage_pipeline(module, &[true, false]);
+        let front = self.create_storage_buffer(initial);
+        let back = self.create_output_buffer::<T>(initial.len());
+        let (dims, info_buffer) = self.create_dispatch_info(initial.len())?;
+        let bind_groups = [
+            self.create_bind_group(&bind_group_layout, &[&front, &back, &info_buffer]),
+            self.create_bind_group(&bind_group_layout, &[&back, &front, &info_buffer]),
+        ];
+
+        // One pass per step, so each step sees the previous step's writes
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        for iteration in 0..iterations {
+            let mut cpass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None });
+            cpass.set_pipeline(&compute_pipeline);
+            cpass.set_bind_group(0, &bind_groups[iteration % 2], &[]);
+            cpass.dispatch(dims.0, dims.1, dims.2);
+        }
+        self.queue.submit(Some(encoder.finish()));
+
+        // Odd step counts finish writing to the back buffer
+        let last = if iterations % 2 == 1 { &back } else { &front };
+        self.read_buffer(last, initial.len()).await
+    }
+
+    /// Runs a ping-pong kernel step by step until it reports convergence or `max_iters` steps
+    /// have run, for iterative solvers. Only a one-element flag is read back per step; the state
+    /// stays on the GPU until the end.
+    ///
+    /// The state is at bindings 0 and 1 as for `ping_pong`, and a u32 flag at `flag_binding`
+    /// is set to 1 before each step, with `DispatchInfo` right after it; bindings in between
+    /// are left unused. `flag_binding` must be at least 2, or the run fails with
+    /// `ComputeError::ReservedBinding`. An invocation that hasn't converged stores 0 to the
+    /// flag, so it stays 1 only when every element has.
+    pub async fn run_until<T: Pod>(
+        &self,
+        module: &wgpu::ShaderModule,
+        initial: &[T],
+        max_iters: usize,
+        flag_binding: u32,
+    ) -> Result<Convergence<T>, ComputeError> {
+        if flag_binding < 2 {
+            return Err(ComputeError::ReservedBinding { binding: flag_binding });
+        }
+        if max_iters == 0 || initial.is_empty() {
+            return Ok(Convergence {
+                state: initial.to_vec(),
+                iterations: 0,
+                converged: false,
+            });
+        }
+
+        let bind_group_layout = self.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: None,
+            entries: &[
+                storage_layout_entry(0, true, None),
+                storage_layout_entry(1, false, None),
+                storage_layout_entry(flag_binding, false, None),
+                uniform_layout_entry(flag_binding + 1, size_of::<DispatchInfo>() as u64),
+            ],
+        });
+        let compute_pipeline = self.create_pipeline(module, &[&bind_group_layout]);
+        let front = self.create_storage_buffer(initial);
+        let back = self.create_output_buffer::<T>(initial.len());
+        let flag_buffer = self.create_storage_buffer(&[1u32]);
+        let (dims, info_buffer) = self.create_dispatch_info(initial.len())?;
+        let bind_group = |current: &wgpu::Buffer, next: &wgpu::Buffer| {
+            let bindings = [(0, current), (1, next), (flag_binding, &*flag_buffer), (flag_binding + 1, &*info_buffer)];
+            let entries = bindings
+                .iter()
+                .map(|&(binding, buffer)| wgpu::BindGroupEntry {
+                    binding,
+                    resource: buffer.as_entire_binding(),
+                })
+                .collect::<Vec<_>>();
+            self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: None,
+                layout: &bind_group_layout,
+                entries: &entries,
+            })
+        };
+        let bind_groups = [bind_group(&*front, &*back), bind_group(&*back, &*front)];
+
+        let mut iterations = 0;
+        let mut converged = false;
+        while iterations < max_iters && !converged {
+            self.queue.write_buffer(&flag_buffer, 0, bytemuck::bytes_of(&1u32));
+            self.dispatch(&compute_pipeline, &[&bind_groups[iterations % 2]], dims);
+            iterations += 1;
+            converged = self.read_buffer::<u32>(&flag_buffer, 1).await?[0] != 0;
+        }
+
+        // Odd step counts finish writing to the back buffer
+        let last = if iterations % 2 == 1 { &back } else { &front };
+        let state = self.read_buffer(last, initial.len()).await?;
+        Ok(Convergence {
+            state,
+            iterations,
+            converged,
+        })
+    }
+}
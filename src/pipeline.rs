@@ -0,0 +1,172 @@
+use bytemuck::Pod;
+
+use std::mem::size_of;
+
+use crate::{align, context::elements_from_bytes, ComputeError, GpuContext};
+
+/// A chain of kernels run in place over one buffer, each stage seeing the previous one's writes.
+///
+/// Every stage gets the data at binding 0 and the `DispatchInfo` uniform at binding 1, as with
+/// `execute_kernel`. The data stays on the GPU between stages.
+pub struct Pipeline<'a> {
+    stages: Vec<&'a wgpu::ShaderModule>,
+    debug_stage: Option<usize>,
+    max_stages: usize,
+}
+
+/// Default for `Pipeline::with_max_stages`.
+pub const DEFAULT_MAX_STAGES: usize = 256;
+
+/// Result of `GpuContext::execute_pipeline`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PipelineOutput<T> {
+    /// The buffer after the last stage.
+    pub output: Vec<T>,
+    /// The buffer right after the stage picked with `Pipeline::debug_readback_stage`, if any.
+    pub stage_output: Option<Vec<T>>,
+}
+
+impl<'a> Default for Pipeline<'a> {
+    fn default() -> Self {
+        Self {
+            stages: Vec::new(),
+            debug_stage: None,
+            max_stages: DEFAULT_MAX_STAGES,
+        }
+    }
+}
+
+impl<'a> Pipeline<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Caps the number of stages `stage` accepts, as a guard against a runaway loop building
+    /// the pipeline. Defaults to `DEFAULT_MAX_STAGES`.
+    pub fn with_max_stages(mut self, max_stages: usize) -> Self {
+        self.max_stages = max_stages;
+        self
+    }
+
+    /// Appends a stage running `module`, failing with `ComputeError::TooManyStages` if the
+    /// pipeline already has the maximum number of stages.
+    pub fn stage(mut self, module: &'a wgpu::ShaderModule) -> Result<Self, ComputeError> {
+        self.check_room()?;
+        self.stages.push(module);
+        Ok(self)
+    }
+
+    fn check_room(&self) -> Result<(), ComputeError> {
+        if self.stages.len() >= self.max_stages {
+            return Err(ComputeError::TooManyStages { max: self.max_stages });
+        }
+        Ok(())
+    }
+
+    /// Also reads back the buffer as it is right after stage `index`, for debugging multi-pass
+    /// kernels. The copy is recorded between that stage's pass and the next, so the following
+    /// stages still run on the same buffer.
+    pub fn debug_readback_stage(mut self, index: usize) -> Self {
+        self.debug_stage = Some(index);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.stages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+}
+
+impl GpuContext {
+    /// Runs every stage of `pipeline` over `input` in one submission, with a pass per stage.
+    pub async fn execute_pipeline<T: Pod>(
+        &self,
+        pipeline: &Pipeline<'_>,
+        input: &[T],
+    ) -> Result<PipelineOutput<T>, ComputeError> {
+        if let Some(index) = pipeline.debug_stage {
+            if index >= pipeline.stages.len() {
+                return Err(ComputeError::IndexOutOfRange {
+                    index,
+                    len: pipeline.stages.len(),
+                });
+            }
+        }
+
+        // Zero-sized buffers can't be mapped, and there's nothing for the stages to do
+        if input.is_empty() {
+            return Ok(PipelineOutput {
+                output: Vec::new(),
+                stage_output: pipeline.debug_stage.map(|_| Vec::new()),
+            });
+        }
+
+        let storage_buffer = self.create_storage_buffer(input);
+        let (dims, info_buffer) = self.create_dispatch_info(input.len())?;
+        let stages = pipeline
+            .stages
+            .iter()
+            .map(|module| {
+                let (bind_group_layout, compute_pipeline) = self.create_storage_pipeline(module, &[false]);
+                let bind_group = self.create_bind_group(&bind_group_layout, &[&storage_buffer, &info_buffer]);
+                (compute_pipeline, bind_group)
+            })
+            .collect::<Vec<_>>();
+
+        let copy_size = align::copy_size((input.len() * size_of::<T>()) as wgpu::BufferAddress);
+        let debug_buffer = pipeline.debug_stage.map(|_| self.create_readback_buffer(copy_size));
+
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        for (index, (compute_pipeline, bind_group)) in stages.iter().enumerate() {
+            {
+                let mut cpass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None });
+                cpass.set_pipeline(compute_pipeline);
+                cpass.set_bind_group(0, bind_group, &[]);
+                cpass.dispatch(dims.0, dims.1, dims.2);
+            }
+            if pipeline.debug_stage == Some(index) {
+                if let Some(debug_buffer) = &debug_buffer {
+                    encoder.copy_buffer_to_buffer(&storage_buffer, 0, debug_buffer, 0, copy_size);
+                }
+            }
+        }
+        self.queue.submit(Some(encoder.finish()));
+
+        let stage_output = match &debug_buffer {
+            Some(debug_buffer) => Some(
+                self.map_read(debug_buffer, (input.len() * size_of::<T>()) as u64, elements_from_bytes)
+                    .await??,
+            ),
+            None => None,
+        };
+        let output = self.read_buffer(&storage_buffer, input.len()).await?;
+        Ok(PipelineOutput { output, stage_output })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_max_stages_rejects_first_stage() {
+        let pipeline = Pipeline::new().with_max_stages(0);
+        assert!(matches!(pipeline.check_room(), Err(ComputeError::TooManyStages { max: 0 })));
+        assert!(Pipeline::new().check_room().is_ok());
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn stages_over_the_limit() {
+        let context = futures::executor::block_on(GpuContext::new()).unwrap();
+        let shader = crate::shader_descriptor(include_bytes!(env!("compute.spv"))).unwrap();
+        let module = context.create_shader_module(&shader);
+
+        let pipeline = Pipeline::new().with_max_stages(2).stage(&module).unwrap().stage(&module).unwrap();
+        assert_eq!(pipeline.len(), 2);
+        assert!(matches!(pipeline.stage(&module), Err(ComputeError::TooManyStages { max: 2 })));
+    }
+}
@@ -0,0 +1,194 @@
+use bytemuck::Pod;
+
+use std::{
+    collections::HashMap,
+    mem::size_of,
+    num::NonZeroU64,
+    ops::Deref,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+use crate::{
+    align,
+    context::{elements_from_bytes, STORAGE_USAGE},
+    ComputeError, GpuContext, TrackedBuffer,
+};
+
+// Smallest bucket, so tiny buffers like uniforms all share one
+const MIN_BUCKET_SIZE: wgpu::BufferAddress = 256;
+
+/// Free buffers of a `GpuContext`, bucketed by usage and power-of-two size, handed out again
+/// instead of allocating fresh ones for every run. Buffers go back to the pool when their
+/// `PooledBuffer` is dropped and are only freed with the pool.
+pub struct BufferPool<'a> {
+    context: &'a GpuContext,
+    free: Mutex<HashMap<(wgpu::BufferUsage, wgpu::BufferAddress), Vec<TrackedBuffer>>>,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+
+/// Counters for judging how well a `BufferPool` is reused.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PoolStats {
+    /// Buffers handed out from the free list.
+    pub hits: usize,
+    /// Buffers that had to be allocated.
+    pub misses: usize,
+    /// Buffers currently waiting in the pool.
+    pub free: usize,
+}
+
+/// A buffer borrowed from a `BufferPool`. It may be larger than requested, up to the next
+/// power of two, and its contents are whatever the previous user left.
+pub struct PooledBuffer<'p, 'a> {
+    pool: &'p BufferPool<'a>,
+    buffer: Option<TrackedBuffer>,
+    usage: wgpu::BufferUsage,
+    bucket_size: wgpu::BufferAddress,
+}
+
+impl GpuContext {
+    pub fn buffer_pool(&self) -> BufferPool<'_> {
+        BufferPool {
+            context: self,
+            free: Mutex::default(),
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl<'a> BufferPool<'a> {
+    /// Hands out a free buffer of at least `size` bytes with exactly `usage`, allocating one if
+    /// the bucket is empty.
+    pub fn acquire(&self, size: wgpu::BufferAddress, usage: wgpu::BufferUsage) -> PooledBuffer<'_, 'a> {
+        let bucket_size = size.max(MIN_BUCKET_SIZE).next_power_of_two();
+        let reused = self
+            .free
+            .lock()
+            .unwrap()
+            .get_mut(&(usage, bucket_size))
+            .and_then(Vec::pop);
+        let buffer = match reused {
+            Some(buffer) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                buffer
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                self.context
+                    .tracker
+                    .track(self.context.device.create_buffer(&wgpu::BufferDescriptor {
+                        label: None,
+                        size: bucket_size,
+                        usage,
+                        mapped_at_creation: false,
+                    }))
+            }
+        };
+        PooledBuffer {
+            pool: self,
+            buffer: Some(buffer),
+            usage,
+            bucket_size,
+        }
+    }
+
+    /// Like `GpuContext::create_storage_buffer`, with the buffer taken from the pool.
+    pub fn upload<T: Pod>(&self, contents: &[T]) -> PooledBuffer<'_, 'a> {
+        let bytes: &[u8] = bytemuck::cast_slice(contents);
+        let size = align::copy_size(bytes.len() as u64);
+        let buffer = self.acquire(size, STORAGE_USAGE);
+
+        // Queue writes must be a multiple of COPY_BUFFER_ALIGNMENT
+        if size > bytes.len() as u64 {
+            let mut padded = bytes.to_vec();
+            padded.resize(size as usize, 0);
+            self.context.queue.write_buffer(&buffer, 0, &padded);
+        } else if size > 0 {
+            self.context.queue.write_buffer(&buffer, 0, bytes);
+        }
+        buffer
+    }
+
+    /// Like `GpuContext::execute_kernel`, with the storage and readback buffers taken from the
+    /// pool. The storage buffer is bound with exactly the input's size, so a bigger pooled
+    /// buffer doesn't change what the kernel sees as its array length.
+    pub async fn execute_kernel<T: Pod>(&self, module: &wgpu::ShaderModule, input: &[T]) -> Result<Vec<T>, ComputeError> {
+        if input.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let context = self.context;
+        let (bind_group_layout, compute_pipeline) = context.create_storage_pipeline(module, &[false]);
+        let storage_buffer = self.upload(input);
+        let (dims, info_buffer) = context.create_dispatch_info(input.len())?;
+        let byte_len = (input.len() * size_of::<T>()) as u64;
+        let copy_size = align::copy_size(byte_len);
+        let bind_group = context.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                        buffer: &storage_buffer,
+                        offset: 0,
+                        size: NonZeroU64::new(copy_size),
+                    }),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: info_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let readback_buffer = self.acquire(copy_size, wgpu::BufferUsage::MAP_READ | wgpu::BufferUsage::COPY_DST);
+        let mut encoder = context.device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        {
+            let mut cpass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None });
+            cpass.set_pipeline(&compute_pipeline);
+            cpass.set_bind_group(0, &bind_group, &[]);
+            cpass.dispatch(dims.0, dims.1, dims.2);
+        }
+        encoder.copy_buffer_to_buffer(&storage_buffer, 0, &readback_buffer, 0, copy_size);
+        context.queue.submit(Some(encoder.finish()));
+
+        // Maps only the copied range, the rest of a pooled buffer is stale
+        context.map_read(&readback_buffer, byte_len, elements_from_bytes).await?
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        PoolStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            free: self.free.lock().unwrap().values().map(Vec::len).sum(),
+        }
+    }
+}
+
+impl Deref for PooledBuffer<'_, '_> {
+    type Target = wgpu::Buffer;
+
+    fn deref(&self) -> &wgpu::Buffer {
+        self.buffer.as_ref().unwrap()
+    }
+}
+
+impl Drop for PooledBuffer<'_, '_> {
+    fn drop(&mut self) {
+        if let Some(buffer) = self.buffer.take() {
+            self.pool
+                .free
+                .lock()
+                .unwrap()
+                .entry((self.usage, self.bucket_size))
+                .or_default()
+                .push(buffer);
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use bytemuck::Pod;
+
+use crate::{ComputeError, GpuContext};
+
+impl GpuContext {
+    /// Runs `module` over `input` and then `predicate` over its output, reading back a single
+    /// pass/fail flag instead of the whole output. Handy in tests on large inputs.
+    ///
+    /// The predicate reads the output at binding 0 and gets a one-element `u32` flag buffer at
+    /// binding 1, initialised to 1, followed by the `DispatchInfo` uniform. It should store 0 to
+    /// the flag for any element failing the check; see `kernels/all_positive`.
+    pub async fn assert_kernel<T: Pod>(
+        &self,
+        module: &wgpu::ShaderModule,
+        input: &[T],
+        predicate: &wgpu::ShaderModule,
+    ) -> Result<bool, ComputeError> {
+        // Vacuously true, and there'd be nothing to bind
+        if input.is_empty() {
+            return Ok(true);
+        }
+
+        let (bind_group_layout, compute_pipeline) = self.create_storage_pipeline(module, &[false]);
+        let (predicate_layout, predicate_pipeline) = self.create_storage_pipeline(predicate, &[true, false]);
+
+        let storage_buffer = self.create_storage_buffer(input);
+        let flag_buffer = self.create_storage_buffer(&[1u32]);
+        let (dims, info_buffer) = self.create_dispatch_info(input.len())?;
+        let bind_group = self.create_bind_group(&bind_group_layout, &[&storage_buffer, &info_buffer]);
+        let predicate_bind_group =
+            self.create_bind_group(&predicate_layout, &[&storage_buffer, &flag_buffer, &info_buffer]);
+
+        // Queue order is enough for the predicate to see the kernel's writes
+        self.dispatch(&compute_pipeline, &[&bind_group], dims);
+        self.dispatch(&predicate_pipeline, &[&predicate_bind_group], dims);
+
+        let flag = self.read_buffer::<u32>(&flag_buffer, 1).await?;
+        Ok(flag[0] != 0)
+    }
+}
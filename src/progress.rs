@@ -0,0 +1,86 @@
+use bytemuck::Pod;
+
+use std::convert::TryInto;
+
+use crate::{dispatch::WORKGROUP_SIZE, ComputeError, GpuContext, TrackedBuffer};
+
+/// Number of submissions `execute_kernel_with_progress` splits a dispatch into, bounding how
+/// many progress reports it makes.
+const PROGRESS_SLICES: usize = 16;
+
+impl GpuContext {
+    /// Like `execute_kernel`, but calls `progress(done, total)` while the kernel runs, with
+    /// `done` read from a counter the kernel itself increments.
+    ///
+    /// The kernel gets the data at binding 0, a one-element `u32` counter at binding 1,
+    /// initialised to 0, and the `DispatchInfo` uniform at binding 2. The dispatch is split
+    /// into up to 16 slices, each submitted on its own with the counter copied out after it,
+    /// so the kernel has to add `DispatchInfo::offset` to its index before indexing the data.
+    /// Each invocation should atomically add the number of elements it finished to the
+    /// counter; see `kernels/progress`.
+    ///
+    /// The next slice is queued before waiting for the previous one's counter, so the GPU never
+    /// runs dry. A context in `PollMode::Wait` blocks until the queued slice finishes too, so
+    /// its reports lag one slice behind; `PollMode::Poll` and the background poller report each
+    /// slice as it completes.
+    pub async fn execute_kernel_with_progress<T: Pod>(
+        &self,
+        module: &wgpu::ShaderModule,
+        input: &[T],
+        mut progress: impl FnMut(usize, usize),
+    ) -> Result<Vec<T>, ComputeError> {
+        if input.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let (bind_group_layout, compute_pipeline) = self.create_storage_pipeline(module, &[false, false]);
+        let storage_buffer = self.create_storage_buffer(input);
+        let counter_buffer = self.create_storage_buffer(&[0u32]);
+
+        // Whole workgroups per slice, so only the last one has idle invocations
+        let workgroup = WORKGROUP_SIZE as usize;
+        let slice_len = (input.len() + PROGRESS_SLICES - 1) / PROGRESS_SLICES;
+        let slice_len = (slice_len + workgroup - 1) / workgroup * workgroup;
+
+        let mut reported = 0;
+        let mut report = |count: u32| {
+            let count = (count as usize).min(input.len());
+            if count != reported {
+                progress(count, input.len());
+                reported = count;
+            }
+        };
+
+        let mut pending: Option<TrackedBuffer> = None;
+        for start in (0..input.len()).step_by(slice_len) {
+            let len = slice_len.min(input.len() - start);
+            let (dims, info_buffer) = self.create_dispatch_info_at(len, WORKGROUP_SIZE, start)?;
+            let bind_group = self.create_bind_group(&bind_group_layout, &[&storage_buffer, &counter_buffer, &info_buffer]);
+            let readback_buffer = self.create_readback_buffer(wgpu::COPY_BUFFER_ALIGNMENT);
+
+            let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+            {
+                let mut cpass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None });
+                cpass.set_pipeline(&compute_pipeline);
+                cpass.set_bind_group(0, &bind_group, &[]);
+                cpass.dispatch(dims.0, dims.1, dims.2);
+            }
+            encoder.copy_buffer_to_buffer(&counter_buffer, 0, &readback_buffer, 0, wgpu::COPY_BUFFER_ALIGNMENT);
+            self.queue.submit(Some(encoder.finish()));
+
+            if let Some(previous) = pending.replace(readback_buffer) {
+                report(self.read_counter(&previous).await?);
+            }
+        }
+        if let Some(last) = pending {
+            report(self.read_counter(&last).await?);
+        }
+
+        self.read_buffer(&storage_buffer, input.len()).await
+    }
+
+    async fn read_counter(&self, readback_buffer: &wgpu::Buffer) -> Result<u32, ComputeError> {
+        self.map_read(readback_buffer, 4, |data| u32::from_ne_bytes(data.try_into().unwrap()))
+            .await
+    }
+}
@@ -0,0 +1,139 @@
+use crate::{
+    context::{storage_layout_entry, uniform_layout_entry},
+    shader_descriptor, ComputeError, GpuContext,
+};
+
+/// What kind of buffer a shader expects at a binding.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BindingKind {
+    Storage { read_only: bool },
+    Uniform,
+}
+
+/// A buffer binding declared by a shader module.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReflectedBinding {
+    pub group: u32,
+    pub binding: u32,
+    pub kind: BindingKind,
+}
+
+/// Lists the buffer bindings a SPIR-V module declares, sorted by group then binding, so layouts
+/// can be generated from the shader instead of written by hand.
+///
+/// Fails with `ComputeError::UnsupportedShaderFeature` for modules naga can't parse and for
+/// bindings that aren't buffers, like textures and samplers.
+pub fn reflect_bindings(spirv: &[u8]) -> Result<Vec<ReflectedBinding>, ComputeError> {
+    let module = naga::front::spv::parse_u8_slice(spirv, &Default::default())
+        .map_err(|e| ComputeError::UnsupportedShaderFeature(format!("{:?}", e)))?;
+
+    let mut bindings = Vec::new();
+    for (_, var) in module.global_variables.iter() {
+        let resource = match &var.binding {
+            Some(resource) => resource,
+            None => continue,
+        };
+        let kind = match var.class {
+            naga::StorageClass::Storage => BindingKind::Storage {
+                read_only: !var.storage_access.contains(naga::StorageAccess::STORE),
+            },
+            naga::StorageClass::Uniform => BindingKind::Uniform,
+            class => {
+                return Err(ComputeError::UnsupportedShaderFeature(format!(
+                    "binding {} in group {} is not a buffer ({:?})",
+                    resource.binding, resource.group, class
+                )))
+            }
+        };
+        bindings.push(ReflectedBinding {
+            group: resource.group,
+            binding: resource.binding,
+            kind,
+        });
+    }
+    bindings.sort_by_key(|binding| (binding.group, binding.binding));
+    Ok(bindings)
+}
+
+impl GpuContext {
+    /// Creates one bind group layout per group up to the highest one in `bindings`, with groups
+    /// the shader doesn't use left empty.
+    pub fn create_reflected_layouts(&self, bindings: &[ReflectedBinding]) -> Vec<wgpu::BindGroupLayout> {
+        let group_count = bindings.iter().map(|binding| binding.group + 1).max().unwrap_or(0);
+        (0..group_count)
+            .map(|group| {
+                let entries = bindings
+                    .iter()
+                    .filter(|binding| binding.group == group)
+                    .map(|binding| match binding.kind {
+                        BindingKind::Storage { read_only } => storage_layout_entry(binding.binding, read_only, None),
+                        BindingKind::Uniform => uniform_layout_entry(binding.binding, 0),
+                    })
+                    .collect::<Vec<_>>();
+                self.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: None,
+                    entries: &entries,
+                })
+            })
+            .collect()
+    }
+
+    /// Dispatches `workgroups` workgroups of the kernel in `spirv` with a layout reflected from
+    /// the shader itself. `groups[g]` holds the buffers for group `g` in binding order, and must
+    /// match the shader's declarations in count or the run fails with
+    /// `ComputeError::BindingMismatch`. Results stay in the buffers for `read_buffer`.
+    pub async fn execute_kernel_reflected(
+        &self,
+        spirv: &[u8],
+        groups: &[&[&wgpu::Buffer]],
+        workgroups: (u32, u32, u32),
+    ) -> Result<(), ComputeError> {
+        let bindings = reflect_bindings(spirv)?;
+        let layouts = self.create_reflected_layouts(&bindings);
+        if groups.len() != layouts.len() {
+            return Err(ComputeError::BindingMismatch {
+                group: None,
+                expected: layouts.len(),
+                actual: groups.len(),
+            });
+        }
+
+        let mut bind_groups = Vec::with_capacity(layouts.len());
+        for (group, (layout, buffers)) in layouts.iter().zip(groups).enumerate() {
+            let declared = bindings
+                .iter()
+                .filter(|binding| binding.group == group as u32)
+                .collect::<Vec<_>>();
+            if declared.len() != buffers.len() {
+                return Err(ComputeError::BindingMismatch {
+                    group: Some(group as u32),
+                    expected: declared.len(),
+                    actual: buffers.len(),
+                });
+            }
+
+            // Bind by the declared binding numbers, which needn't be contiguous
+            let entries = declared
+                .iter()
+                .zip(buffers.iter())
+                .map(|(binding, buffer)| wgpu::BindGroupEntry {
+                    binding: binding.binding,
+                    resource: buffer.as_entire_binding(),
+                })
+                .collect::<Vec<_>>();
+            bind_groups.push(self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: None,
+                layout,
+                entries: &entries,
+            }));
+        }
+
+        let module = self.try_create_shader_module(&shader_descriptor(spirv)?).await?;
+        let compute_pipeline = self
+            .try_create_pipeline_for_entry(&module, &layouts.iter().collect::<Vec<_>>(), "main_cs", 0)
+            .await?;
+        self.dispatch(&compute_pipeline, &bind_groups.iter().collect::<Vec<_>>(), workgroups);
+        self.device.poll(wgpu::Maintain::Wait);
+        Ok(())
+    }
+}
@@ -0,0 +1,114 @@
+use bytemuck::Pod;
+
+use std::{marker::PhantomData, mem::size_of};
+
+use crate::{context::storage_layout_entry, ComputeError, GpuContext, PassDescriptor, TrackedBuffer};
+
+/// A storage buffer to bind in a `ResourceSet`, looked up later by `name`.
+pub struct NamedBuffer {
+    pub name: String,
+    pub buffer: TrackedBuffer,
+    pub read_only: bool,
+}
+
+impl NamedBuffer {
+    pub fn new(name: impl Into<String>, buffer: TrackedBuffer, read_only: bool) -> Self {
+        Self {
+            name: name.into(),
+            buffer,
+            read_only,
+        }
+    }
+}
+
+/// Buffers uploaded and bound once for kernels dispatched many times over the same inputs,
+/// where only the push constants `P` change between dispatches.
+///
+/// The buffers are bound at bindings `0..n` in the order given, with no `DispatchInfo`
+/// uniform, so kernels should carry any sizes they need in `P`.
+pub struct ResourceSet<'a, P: Pod> {
+    context: &'a GpuContext,
+    compute_pipeline: wgpu::ComputePipeline,
+    bind_group: wgpu::BindGroup,
+    buffers: Vec<NamedBuffer>,
+    _marker: PhantomData<P>,
+}
+
+impl GpuContext {
+    /// Builds the pipeline and bind group for `buffers` once. Requires a context created with
+    /// `Features::PUSH_CONSTANTS` unless `P` is zero-sized.
+    pub fn resource_set<P: Pod>(
+        &self,
+        module: &wgpu::ShaderModule,
+        buffers: Vec<NamedBuffer>,
+    ) -> Result<ResourceSet<'_, P>, ComputeError> {
+        let push_constant_size = size_of::<P>() as u32;
+        if push_constant_size > 0 && !self.device.features().contains(wgpu::Features::PUSH_CONSTANTS) {
+            return Err(ComputeError::UnsupportedFeatures(wgpu::Features::PUSH_CONSTANTS));
+        }
+
+        let entries = buffers
+            .iter()
+            .enumerate()
+            .map(|(binding, buffer)| storage_layout_entry(binding as u32, buffer.read_only, None))
+            .collect::<Vec<_>>();
+        let bind_group_layout = self.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: None,
+            entries: &entries,
+        });
+        let compute_pipeline =
+            self.create_pipeline_with_push_constants(module, &[&bind_group_layout], push_constant_size);
+        let bind_group = self.create_bind_group(
+            &bind_group_layout,
+            &buffers.iter().map(|buffer| &*buffer.buffer).collect::<Vec<_>>(),
+        );
+
+        Ok(ResourceSet {
+            context: self,
+            compute_pipeline,
+            bind_group,
+            buffers,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<'a, P: Pod> ResourceSet<'a, P> {
+    /// Dispatches `workgroups` workgroups against the bound buffers with `push_constants`.
+    pub fn dispatch(&self, workgroups: (u32, u32, u32), push_constants: &P) {
+        self.dispatch_with(workgroups, push_constants, &PassDescriptor::default())
+    }
+
+    /// Like `dispatch`, labelling and timing the pass as described by `pass`.
+    pub fn dispatch_with(&self, (x, y, z): (u32, u32, u32), push_constants: &P, pass: &PassDescriptor<'_>) {
+        let context = self.context;
+        let mut encoder = context
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: pass.label });
+        context.record_pass(&mut encoder, pass, |cpass| {
+            cpass.set_pipeline(&self.compute_pipeline);
+            cpass.set_bind_group(0, &self.bind_group, &[]);
+            if size_of::<P>() > 0 {
+                cpass.set_push_constants(0, bytemuck::bytes_of(push_constants));
+            }
+            cpass.dispatch(x, y, z);
+        });
+        context.queue.submit(Some(encoder.finish()));
+    }
+
+    pub fn buffer(&self, name: &str) -> Option<&TrackedBuffer> {
+        self.buffers
+            .iter()
+            .find(|buffer| buffer.name == name)
+            .map(|buffer| &buffer.buffer)
+    }
+
+    /// Reads back the first `len` elements of the buffer called `name`, or `None` if there is
+    /// no such buffer. Only buffers made with `create_storage_buffer` can be copied from.
+    pub async fn read<T: Pod>(&self, name: &str, len: usize) -> Option<Result<Vec<T>, ComputeError>> {
+        match self.buffer(name) {
+            Some(buffer) => Some(self.context.read_buffer(buffer, len).await),
+            None => None,
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use bytemuck::Pod;
+
+use std::{mem::size_of, ops::ControlFlow};
+
+use crate::{ComputeError, GpuContext};
+
+impl GpuContext {
+    /// Like `execute_kernel`, but calls `f` on each output element while the readback buffer
+    /// is still mapped instead of collecting them into a `Vec`. Iteration stops as soon as `f`
+    /// returns `ControlFlow::Break`, which is passed back so callers can tell an early exit
+    /// from a full scan.
+    pub async fn execute_kernel_scan<T: Pod>(
+        &self,
+        module: &wgpu::ShaderModule,
+        input: &[T],
+        mut f: impl FnMut(&T) -> ControlFlow<()>,
+    ) -> Result<ControlFlow<()>, ComputeError> {
+        if input.is_empty() {
+            return Ok(ControlFlow::Continue(()));
+        }
+
+        let (bind_group_layout, compute_pipeline) = self.create_storage_pipeline(module, &[false]);
+        let storage_buffer = self.create_storage_buffer(input);
+        let (dims, info_buffer) = self.create_dispatch_info(input.len())?;
+        let bind_group = self.create_bind_group(&bind_group_layout, &[&storage_buffer, &info_buffer]);
+        let size = (input.len() * size_of::<T>()) as wgpu::BufferAddress;
+
+        self.dispatch(&compute_pipeline, &[&bind_group], dims);
+        self.with_mapped(&storage_buffer, size, |data| {
+            // Copy each element out since the mapped bytes needn't be aligned for T
+            let mut element = T::zeroed();
+            for bytes in data.chunks_exact(size_of::<T>()) {
+                bytemuck::bytes_of_mut(&mut element).copy_from_slice(bytes);
+                if let ControlFlow::Break(()) = f(&element) {
+                    return ControlFlow::Break(());
+                }
+            }
+            ControlFlow::Continue(())
+        })
+        .await
+    }
+}
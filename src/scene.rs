@@ -0,0 +1,53 @@
+use bytemuck::Pod;
+
+use crate::{
+    context::{storage_entries, storage_layout_entry},
+    ComputeError, GpuContext, TrackedBuffer,
+};
+
+/// Immutable data (triangles, BVH nodes, ...) uploaded once into a read-only storage buffer
+/// with its own bind group, so it can be attached to many dispatches without re-uploading.
+///
+/// Kernels see the scene at `descriptor_set = 1, binding = 0`.
+pub struct SceneBinding {
+    layout: wgpu::BindGroupLayout,
+    bind_group: wgpu::BindGroup,
+    _buffer: TrackedBuffer,
+}
+
+impl GpuContext {
+    pub fn create_scene_binding<S: Pod>(&self, scene: &[S]) -> SceneBinding {
+        let buffer = self.create_input_buffer(scene);
+        let layout = self.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: None,
+            entries: &[storage_layout_entry(0, true, None)],
+        });
+        let bind_group = self.create_bind_group(&layout, &[&buffer]);
+        SceneBinding {
+            layout,
+            bind_group,
+            _buffer: buffer,
+        }
+    }
+
+    /// Like `execute_kernel`, with `scene` attached as descriptor set 1.
+    pub async fn execute_kernel_with_scene<T: Pod>(
+        &self,
+        module: &wgpu::ShaderModule,
+        input: &[T],
+        scene: &SceneBinding,
+    ) -> Result<Vec<T>, ComputeError> {
+        let bind_group_layout = self.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: None,
+            entries: &storage_entries(&[false]),
+        });
+        let compute_pipeline = self.create_pipeline(module, &[&bind_group_layout, &scene.layout]);
+
+        let storage_buffer = self.create_storage_buffer(input);
+        let (dims, info_buffer) = self.create_dispatch_info(input.len())?;
+        let bind_group = self.create_bind_group(&bind_group_layout, &[&storage_buffer, &info_buffer]);
+
+        self.dispatch(&compute_pipeline, &[&bind_group, &scene.bind_group], dims);
+        self.read_buffer(&storage_buffer, input.len()).await
+    }
+}
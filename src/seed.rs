@@ -0,0 +1,13 @@
+/// Seed for the invocation handling element `index` of a run seeded with `seed`, as uploaded
+/// for `RunOptions::seed`. Hashed with SplitMix64 so neighbouring invocations get unrelated
+/// seeds and CPU references can reproduce them.
+pub fn seed_for(seed: u64, index: usize) -> u32 {
+    let mut z = seed.wrapping_add((index as u64 + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    (z ^ (z >> 31)) as u32
+}
+
+pub(crate) fn seeds(seed: u64, len: usize) -> Vec<u32> {
+    (0..len).map(|index| seed_for(seed, index)).collect()
+}
@@ -0,0 +1,37 @@
+use shared::{glam::Vec4, Ray};
+
+use crate::{ComputeError, GpuContext};
+
+// Fixed so a failing smoke test can be rerun with the same input
+const SMOKE_SEED: u32 = 0x9e37_79b9;
+
+impl GpuContext {
+    /// Runs the kernel in place over `count` pseudo-random rays and checks that it completes
+    /// and returns `count` elements, without looking at the values. A quick check that a new
+    /// shader build runs on this adapter at all.
+    pub async fn smoke_test(&self, module: &wgpu::ShaderModule, count: usize) -> Result<(), ComputeError> {
+        let mut state = SMOKE_SEED;
+        let mut next = move || {
+            // xorshift32, mapped to [-1, 1)
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as f32 / u32::MAX as f32 * 2.0 - 1.0
+        };
+        let rays = (0..count)
+            .map(|_| Ray {
+                origin: Vec4::new(next(), next(), next(), 0.0),
+                direction: Vec4::new(next(), next(), next(), 0.0),
+            })
+            .collect::<Vec<_>>();
+
+        let output = self.execute_kernel(module, &rays).await?;
+        if output.len() != count {
+            return Err(ComputeError::LengthMismatch {
+                expected: count,
+                actual: output.len(),
+            });
+        }
+        Ok(())
+    }
+}
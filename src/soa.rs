@@ -0,0 +1,197 @@
+//! Conversions between struct-of-arrays columns and the flat buffers kernels read. A kernel
+//! can take its fields either interleaved per element (array-of-structs, like `Vec<Ray>`) or
+//! as whole columns one after another, which often gives more coalesced loads.
+
+use bytemuck::Pod;
+
+use shared::{split_rays, Ray};
+
+use crate::{ComputeError, GpuContext};
+
+/// Interleaves `columns` element by element, so `[xs, ys]` becomes `x0, y0, x1, y1, ...`.
+pub fn interleave<T: Copy>(columns: &[&[T]]) -> Result<Vec<T>, ComputeError> {
+    let len = column_len(columns)?;
+    let mut result = Vec::with_capacity(len * columns.len());
+    for index in 0..len {
+        result.extend(columns.iter().map(|column| column[index]));
+    }
+    Ok(result)
+}
+
+/// Inverse of `interleave`, splitting `data` into `column_count` columns.
+pub fn deinterleave<T: Copy>(data: &[T], column_count: usize) -> Result<Vec<Vec<T>>, ComputeError> {
+    check_whole(data.len(), column_count)?;
+    Ok((0..column_count)
+        .map(|column| data.iter().skip(column).step_by(column_count).copied().collect())
+        .collect())
+}
+
+/// Places `columns` one after another, so `[xs, ys]` becomes `x0, x1, ..., y0, y1, ...`.
+/// Column `c` of element `i` is then at `c * len + i` in the kernel.
+pub fn concat_columns<T: Copy>(columns: &[&[T]]) -> Result<Vec<T>, ComputeError> {
+    column_len(columns)?;
+    Ok(columns.concat())
+}
+
+/// Inverse of `concat_columns`, splitting `data` into `column_count` equal columns.
+pub fn split_columns<T: Copy>(data: &[T], column_count: usize) -> Result<Vec<Vec<T>>, ComputeError> {
+    let len = check_whole(data.len(), column_count)?;
+    Ok(data.chunks(len.max(1)).take(column_count).map(<[T]>::to_vec).collect())
+}
+
+/// Lays `rays` out as six concatenated columns: origin x, y, z, then direction x, y, z.
+pub fn rays_to_columns(rays: &[Ray]) -> Vec<f32> {
+    let column = |f: fn(&Ray) -> f32| rays.iter().map(f).collect::<Vec<_>>();
+    [
+        column(|ray| ray.origin.x),
+        column(|ray| ray.origin.y),
+        column(|ray| ray.origin.z),
+        column(|ray| ray.direction.x),
+        column(|ray| ray.direction.y),
+        column(|ray| ray.direction.z),
+    ]
+    .concat()
+}
+
+/// Inverse of `rays_to_columns`.
+pub fn rays_from_columns(data: &[f32]) -> Result<Vec<Ray>, ComputeError> {
+    let len = check_whole(data.len(), 6)?;
+    let at = |column: usize, index: usize| data[column * len + index];
+    Ok((0..len)
+        .map(|i| Ray {
+            origin: shared::glam::Vec4::new(at(0, i), at(1, i), at(2, i), 0.0),
+            direction: shared::glam::Vec4::new(at(3, i), at(4, i), at(5, i), 0.0),
+        })
+        .collect())
+}
+
+impl GpuContext {
+    /// Runs a ray kernel that reads origins and directions as separate `Vec4` arrays, at
+    /// bindings 0 and 1, and writes one `O` per ray to binding 2, with `DispatchInfo` at
+    /// binding 3. The rays are split with `shared::split_rays` before upload.
+    pub async fn execute_kernel_split_rays<O: Pod>(
+        &self,
+        module: &wgpu::ShaderModule,
+        rays: &[Ray],
+    ) -> Result<Vec<O>, ComputeError> {
+        if rays.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let (origins, directions) = split_rays(rays);
+        let (bind_group_layout, compute_pipeline) = self.create_storage_pipeline(module, &[true, true, false]);
+        let origin_buffer = self.create_input_buffer(&origins);
+        let direction_buffer = self.create_input_buffer(&directions);
+        let output_buffer = self.create_output_buffer::<O>(rays.len());
+        let (dims, info_buffer) = self.create_dispatch_info(rays.len())?;
+        let bind_group = self.create_bind_group(
+            &bind_group_layout,
+            &[&origin_buffer, &direction_buffer, &output_buffer, &info_buffer],
+        );
+
+        self.dispatch(&compute_pipeline, &[&bind_group], dims);
+        self.read_buffer(&output_buffer, rays.len()).await
+    }
+}
+
+/// Length shared by all `columns`, or `LengthMismatch` for the first one that differs.
+fn column_len<T>(columns: &[&[T]]) -> Result<usize, ComputeError> {
+    let expected = columns.first().map_or(0, |column| column.len());
+    match columns.iter().find(|column| column.len() != expected) {
+        Some(column) => Err(ComputeError::LengthMismatch { expected, actual: column.len() }),
+        None => Ok(expected),
+    }
+}
+
+/// Elements per column when `len` values are split into `column_count` columns, failing with
+/// `NoColumns` for zero columns and `LengthMismatch` if `len` doesn't divide evenly.
+fn check_whole(len: usize, column_count: usize) -> Result<usize, ComputeError> {
+    if column_count == 0 {
+        return Err(ComputeError::NoColumns);
+    }
+    if len % column_count != 0 {
+        return Err(ComputeError::LengthMismatch {
+            expected: len - len % column_count,
+            actual: len,
+        });
+    }
+    Ok(len / column_count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::glam::Vec4;
+
+    fn rays() -> Vec<Ray> {
+        (0..5)
+            .map(|i| {
+                let i = i as f32;
+                Ray {
+                    origin: Vec4::new(i, i + 0.25, i + 0.5, 0.0),
+                    direction: Vec4::new(-i, 1.0, i * 2.0, 0.0),
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn interleave_round_trip() {
+        let xs = [1, 2, 3];
+        let ys = [4, 5, 6];
+        let data = interleave(&[&xs, &ys]).unwrap();
+        assert_eq!(data, [1, 4, 2, 5, 3, 6]);
+        assert_eq!(deinterleave(&data, 2).unwrap(), [xs.to_vec(), ys.to_vec()]);
+    }
+
+    #[test]
+    fn concat_round_trip() {
+        let xs = [1, 2, 3];
+        let ys = [4, 5, 6];
+        let data = concat_columns(&[&xs, &ys]).unwrap();
+        assert_eq!(data, [1, 2, 3, 4, 5, 6]);
+        assert_eq!(split_columns(&data, 2).unwrap(), [xs.to_vec(), ys.to_vec()]);
+    }
+
+    #[test]
+    fn rays_columns_round_trip() {
+        let rays = rays();
+        let data = rays_to_columns(&rays);
+        assert_eq!(data.len(), rays.len() * 6);
+        assert_eq!(rays_from_columns(&data).unwrap(), rays);
+        assert!(rays_from_columns(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn split_join_rays_round_trip() {
+        let rays = rays();
+        let (origins, directions) = split_rays(&rays);
+        assert_eq!(shared::join_rays(&origins, &directions), rays);
+    }
+
+    #[test]
+    #[should_panic(expected = "every ray needs an origin and a direction")]
+    fn join_rays_panics_on_mismatch() {
+        let (origins, directions) = split_rays(&rays());
+        shared::join_rays(&origins, &directions[1..]);
+    }
+
+    #[test]
+    fn uneven_columns() {
+        assert!(matches!(
+            interleave(&[&[1, 2][..], &[3][..]]),
+            Err(ComputeError::LengthMismatch { expected: 2, actual: 1 })
+        ));
+        assert!(matches!(
+            deinterleave(&[1, 2, 3], 2),
+            Err(ComputeError::LengthMismatch { expected: 2, actual: 3 })
+        ));
+        assert!(rays_from_columns(&[0.0; 7]).is_err());
+    }
+
+    #[test]
+    fn zero_columns() {
+        assert!(matches!(deinterleave(&[1, 2], 0), Err(ComputeError::NoColumns)));
+        assert!(matches!(split_columns::<i32>(&[], 0), Err(ComputeError::NoColumns)));
+    }
+}
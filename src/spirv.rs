@@ -0,0 +1,126 @@
+use std::{borrow::Cow, convert::TryInto};
+
+use crate::{validate::check_deterministic_words, ComputeError, GpuContext};
+
+const SPIRV_MAGIC: u32 = 0x0723_0203;
+// Magic, version, generator, bound and schema words
+const HEADER_WORDS: usize = 5;
+
+/// Converts a SPIR-V blob to words, failing with `ComputeError::InvalidSpirv` instead of
+/// panicking like `wgpu::util::make_spirv` when it is truncated or isn't SPIR-V at all.
+pub fn spirv_words(spirv: &[u8]) -> Result<Vec<u32>, ComputeError> {
+    if spirv.len() % 4 != 0 {
+        return Err(ComputeError::InvalidSpirv(format!(
+            "length of {} bytes is not a whole number of words",
+            spirv.len()
+        )));
+    }
+    let words = spirv
+        .chunks_exact(4)
+        .map(|word| u32::from_ne_bytes(word.try_into().unwrap()))
+        .collect::<Vec<_>>();
+    if words.len() < HEADER_WORDS {
+        return Err(ComputeError::InvalidSpirv(format!(
+            "{} words is too short for a module header",
+            words.len()
+        )));
+    }
+    if words[0] != SPIRV_MAGIC {
+        return Err(ComputeError::InvalidSpirv(format!("bad magic number {:#010x}", words[0])));
+    }
+    Ok(words)
+}
+
+/// Builds a shader module descriptor for a SPIR-V blob, checked with `spirv_words`.
+pub fn shader_descriptor(spirv: &[u8]) -> Result<wgpu::ShaderModuleDescriptor<'static>, ComputeError> {
+    Ok(wgpu::ShaderModuleDescriptor {
+        label: None,
+        source: wgpu::ShaderSource::SpirV(Cow::Owned(spirv_words(spirv)?)),
+        flags: wgpu::ShaderFlags::default(),
+    })
+}
+
+impl GpuContext {
+    /// Like `create_shader_module`, but returns `ComputeError::InvalidSpirv` for a module the
+    /// device rejects rather than letting wgpu's uncaught error handler panic. With
+    /// `ComputeOptions::deterministic` set, SPIR-V that `check_deterministic` rejects fails
+    /// with its error before reaching the device.
+    pub async fn try_create_shader_module(
+        &self,
+        shader_binary: &wgpu::ShaderModuleDescriptor<'_>,
+    ) -> Result<wgpu::ShaderModule, ComputeError> {
+        // WGSL has no way to ask for fast math, so only SPIR-V needs checking
+        if let (true, wgpu::ShaderSource::SpirV(words)) = (self.deterministic, &shader_binary.source) {
+            check_deterministic_words(words)?;
+        }
+        self.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let module = self.create_shader_module(shader_binary);
+        match self.device.pop_error_scope().await {
+            Some(e) => Err(ComputeError::InvalidSpirv(e.to_string())),
+            None => Ok(module),
+        }
+    }
+
+    /// Like `create_pipeline_for_entry`, but returns `ComputeError::PipelineCreation` when the
+    /// device rejects the pipeline, e.g. for a missing entry point or a layout the module
+    /// doesn't match.
+    pub(crate) async fn try_create_pipeline_for_entry(
+        &self,
+        module: &wgpu::ShaderModule,
+        bind_group_layouts: &[&wgpu::BindGroupLayout],
+        entry_point: &str,
+        push_constant_size: u32,
+    ) -> Result<wgpu::ComputePipeline, ComputeError> {
+        self.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let pipeline = self.create_pipeline_for_entry(module, bind_group_layouts, entry_point, push_constant_size);
+        match self.device.pop_error_scope().await {
+            Some(e) => Err(ComputeError::PipelineCreation(e.to_string())),
+            None => Ok(pipeline),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header() -> Vec<u8> {
+        [SPIRV_MAGIC, 0x0001_0300, 0, 1, 0]
+            .iter()
+            .flat_map(|word| word.to_ne_bytes().to_vec())
+            .collect()
+    }
+
+    #[test]
+    fn accepts_header() {
+        assert_eq!(spirv_words(&header()).unwrap().len(), HEADER_WORDS);
+    }
+
+    #[test]
+    fn rejects_truncated_blob() {
+        let blob = header();
+        assert!(matches!(spirv_words(&blob[..8]), Err(ComputeError::InvalidSpirv(_))));
+        assert!(matches!(spirv_words(&[]), Err(ComputeError::InvalidSpirv(_))));
+        assert!(shader_descriptor(&blob[..8]).is_err());
+    }
+
+    #[test]
+    fn rejects_misaligned_blob() {
+        let blob = header();
+        assert!(matches!(spirv_words(&blob[..blob.len() - 1]), Err(ComputeError::InvalidSpirv(_))));
+        assert!(shader_descriptor(&blob[..blob.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn rejects_bad_magic() {
+        let mut blob = header();
+        blob[0] ^= 0xff;
+        assert!(matches!(spirv_words(&blob), Err(ComputeError::InvalidSpirv(_))));
+    }
+
+    #[test]
+    fn reflect_rejects_truncated_blob() {
+        let blob = header();
+        assert!(crate::reflect_bindings(&blob[..blob.len() - 3]).is_err());
+    }
+}
@@ -0,0 +1,105 @@
+use bytemuck::Pod;
+
+use futures::FutureExt;
+
+use std::{mem::size_of, num::NonZeroU64};
+
+use crate::{align, context::STORAGE_USAGE, ComputeError, GpuContext, TrackedBuffer};
+
+// Below this, the extra submission costs more than create_buffer_init's copy
+const BELT_THRESHOLD: usize = 1 << 20;
+
+impl GpuContext {
+    /// Creates a storage buffer of `len` elements filled by `fill` through a mapped `MAP_WRITE`
+    /// staging buffer, which is then copied to it on the GPU.
+    ///
+    /// Unlike `create_storage_buffer`, the input never has to exist as a `Vec` first, so this
+    /// wins when the data is generated or decoded on the fly and is large enough that building
+    /// it twice matters. For data already in memory, `create_storage_buffer` is just as fast.
+    pub fn create_storage_buffer_with<T: Pod>(&self, len: usize, fill: impl FnOnce(&mut [T])) -> TrackedBuffer {
+        let size = align::copy_size((len * size_of::<T>()) as u64);
+        let storage_buffer = self.tracker.track(self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size,
+            usage: STORAGE_USAGE,
+            mapped_at_creation: false,
+        }));
+        if size == 0 {
+            fill(&mut []);
+            return storage_buffer;
+        }
+
+        let staging_buffer = self.tracker.track(self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size,
+            usage: wgpu::BufferUsage::MAP_WRITE | wgpu::BufferUsage::COPY_SRC,
+            mapped_at_creation: true,
+        }));
+        {
+            let mut data = staging_buffer.slice(..).get_mapped_range_mut();
+            let bytes = &mut data[..len * size_of::<T>()];
+            match bytemuck::try_cast_slice_mut(bytes) {
+                Ok(elements) => fill(elements),
+                // Mapped ranges are only guaranteed 8-byte alignment, so fill a copy for
+                // types aligned more strictly than that
+                Err(_) => {
+                    let mut elements = vec![T::zeroed(); len];
+                    fill(&mut elements);
+                    bytes.copy_from_slice(bytemuck::cast_slice(&elements));
+                }
+            }
+        }
+        staging_buffer.unmap();
+
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        encoder.copy_buffer_to_buffer(&staging_buffer, 0, &storage_buffer, 0, size);
+        self.queue.submit(Some(encoder.finish()));
+        storage_buffer
+    }
+
+    /// Uploads `contents` into a storage buffer through the context's staging belt, whose
+    /// mapped chunks are reused across calls instead of a fresh staging allocation per upload
+    /// as with `create_storage_buffer`. Inputs under 1 MiB skip the belt and use
+    /// `create_storage_buffer`, which is cheaper for them.
+    ///
+    /// The copy is submitted before the returned buffer is handed out, so dispatches submitted
+    /// afterwards see the data. The future resolves once the belt has its chunks back.
+    pub async fn upload_storage_buffer<T: Pod>(&self, contents: &[T]) -> Result<TrackedBuffer, ComputeError> {
+        let bytes: &[u8] = bytemuck::cast_slice(contents);
+        if bytes.len() < BELT_THRESHOLD {
+            return Ok(self.create_storage_buffer(contents));
+        }
+
+        // Belt writes are copies, so round the size up and zero the tail
+        let size = align::copy_size(bytes.len() as u64);
+        let storage_buffer = self.tracker.track(self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size,
+            usage: STORAGE_USAGE,
+            mapped_at_creation: false,
+        }));
+
+        let recall = {
+            let mut belt = self.belt.lock().unwrap();
+            let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+            {
+                let mut view = belt.write_buffer(
+                    &mut encoder,
+                    &storage_buffer,
+                    0,
+                    NonZeroU64::new(size).unwrap(),
+                    &self.device,
+                );
+                view[..bytes.len()].copy_from_slice(bytes);
+                view[bytes.len()..].fill(0);
+            }
+            belt.finish();
+            self.queue.submit(Some(encoder.finish()));
+            belt.recall()
+        };
+
+        // Chunks only come back to the belt once their map callbacks fire
+        self.wait_for(recall.map(Ok)).await?;
+        Ok(storage_buffer)
+    }
+}
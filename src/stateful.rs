@@ -0,0 +1,68 @@
+use bytemuck::Pod;
+
+use std::marker::PhantomData;
+
+use crate::{ComputeError, GpuContext, TrackedBuffer};
+
+/// A kernel whose storage buffer persists across dispatches, for simulations where each step
+/// reads the state the previous step left behind. State only crosses to the CPU on request.
+pub struct StatefulKernel<'a, T: Pod> {
+    context: &'a GpuContext,
+    compute_pipeline: wgpu::ComputePipeline,
+    bind_group: wgpu::BindGroup,
+    buffer: TrackedBuffer,
+    _info_buffer: TrackedBuffer,
+    dims: (u32, u32, u32),
+    len: usize,
+    _marker: PhantomData<T>,
+}
+
+impl GpuContext {
+    pub fn stateful_kernel<T: Pod>(
+        &self,
+        module: &wgpu::ShaderModule,
+        initial: &[T],
+    ) -> Result<StatefulKernel<'_, T>, ComputeError> {
+        let (bind_group_layout, compute_pipeline) = self.create_storage_pipeline(module, &[false]);
+        let buffer = self.create_storage_buffer(initial);
+        let (dims, info_buffer) = self.create_dispatch_info(initial.len())?;
+        let bind_group = self.create_bind_group(&bind_group_layout, &[&buffer, &info_buffer]);
+        Ok(StatefulKernel {
+            context: self,
+            compute_pipeline,
+            bind_group,
+            buffer,
+            _info_buffer: info_buffer,
+            dims,
+            len: initial.len(),
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<'a, T: Pod> StatefulKernel<'a, T> {
+    /// Dispatches the kernel once over the current state, in place.
+    pub fn step(&self) {
+        self.context
+            .dispatch(&self.compute_pipeline, &[&self.bind_group], self.dims);
+    }
+
+    /// Copies the current state back to the CPU.
+    pub async fn read_state(&self) -> Result<Vec<T>, ComputeError> {
+        self.context.read_buffer(&self.buffer, self.len).await
+    }
+
+    /// Overwrites the state, e.g. with a snapshot previously taken by `read_state`.
+    pub fn write_state(&self, state: &[T]) -> Result<(), ComputeError> {
+        if state.len() != self.len {
+            return Err(ComputeError::LengthMismatch {
+                expected: self.len,
+                actual: state.len(),
+            });
+        }
+        self.context
+            .queue
+            .write_buffer(&self.buffer, 0, bytemuck::cast_slice(state));
+        Ok(())
+    }
+}
@@ -0,0 +1,45 @@
+use std::{fmt, time::Duration};
+
+/// Diagnostics collected during a kernel run, for reporting alongside the result.
+#[derive(Clone, Debug)]
+pub struct RunStats {
+    /// Name of the adapter the kernel ran on.
+    pub adapter: String,
+    /// Backend the adapter was driven through, which may not be the first one tried.
+    pub backend: wgpu::Backend,
+    pub elements: usize,
+    pub bytes_uploaded: u64,
+    pub bytes_read_back: u64,
+    /// Workgroup counts passed to `dispatch`.
+    pub dispatch: (u32, u32, u32),
+    /// Invocations per workgroup the dispatch was computed for.
+    pub workgroup_size: u32,
+    /// Wall-clock time of the measured run, from upload to readback, excluding warmup.
+    pub duration: Duration,
+}
+
+impl RunStats {
+    /// Total invocations launched, which covers the input when it is at least `elements`.
+    pub fn invocations(&self) -> u64 {
+        let (x, y, z) = self.dispatch;
+        x as u64 * y as u64 * z as u64 * self.workgroup_size as u64
+    }
+
+    /// Invocations launched past the end of the input, which only bounds-check and return.
+    /// A large share suggests a workgroup size closer to the input length.
+    pub fn wasted_invocations(&self) -> u64 {
+        self.invocations().saturating_sub(self.elements as u64)
+    }
+}
+
+impl fmt::Display for RunStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Adapter:         {} ({:?})", self.adapter, self.backend)?;
+        writeln!(f, "Elements:        {}", self.elements)?;
+        writeln!(f, "Bytes uploaded:  {}", self.bytes_uploaded)?;
+        writeln!(f, "Bytes read back: {}", self.bytes_read_back)?;
+        writeln!(f, "Dispatch:        {:?} x {}", self.dispatch, self.workgroup_size)?;
+        writeln!(f, "Invocations:     {} ({} wasted)", self.invocations(), self.wasted_invocations())?;
+        write!(f, "Total time:      {:?}", self.duration)
+    }
+}
@@ -0,0 +1,83 @@
+use bytemuck::Pod;
+
+use std::{marker::PhantomData, mem::size_of};
+
+use crate::{
+    align,
+    context::{storage_entries, uniform_layout_entry},
+    ComputeError, GpuContext, TrackedBuffer,
+};
+
+/// Input uploaded once for parameter sweeps, where only a small uniform changes between runs.
+///
+/// The kernel reads the input at binding 0, writes one `O` per input element at binding 1, sees
+/// the `DispatchInfo` uniform at binding 2 and the sweep parameters `U` at binding 3.
+pub struct PreparedInput<'a, I: Pod, O: Pod, U: Pod> {
+    context: &'a GpuContext,
+    compute_pipeline: wgpu::ComputePipeline,
+    bind_group: wgpu::BindGroup,
+    output_buffer: TrackedBuffer,
+    uniform_buffer: TrackedBuffer,
+    _input_buffer: TrackedBuffer,
+    _info_buffer: TrackedBuffer,
+    dims: (u32, u32, u32),
+    len: usize,
+    _marker: PhantomData<(I, O, U)>,
+}
+
+impl GpuContext {
+    pub fn prepare_input<I: Pod, O: Pod, U: Pod>(
+        &self,
+        module: &wgpu::ShaderModule,
+        input: &[I],
+    ) -> Result<PreparedInput<'_, I, O, U>, ComputeError> {
+        let mut entries = storage_entries(&[true, false]);
+        entries.push(uniform_layout_entry(3, size_of::<U>() as u64));
+        let bind_group_layout = self.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: None,
+            entries: &entries,
+        });
+        let compute_pipeline = self.create_pipeline(module, &[&bind_group_layout]);
+
+        let input_buffer = self.create_input_buffer(input);
+        let output_buffer = self.create_output_buffer::<O>(input.len());
+        let (dims, info_buffer) = self.create_dispatch_info(input.len())?;
+        let uniform_buffer = self.tracker.track(self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: align::copy_size(size_of::<U>() as u64),
+            usage: wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
+            mapped_at_creation: false,
+        }));
+        let bind_group = self.create_bind_group(
+            &bind_group_layout,
+            &[&input_buffer, &output_buffer, &info_buffer, &uniform_buffer],
+        );
+
+        Ok(PreparedInput {
+            context: self,
+            compute_pipeline,
+            bind_group,
+            output_buffer,
+            uniform_buffer,
+            _input_buffer: input_buffer,
+            _info_buffer: info_buffer,
+            dims,
+            len: input.len(),
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<'a, I: Pod, O: Pod, U: Pod> PreparedInput<'a, I, O, U> {
+    /// Uploads `uniform`, dispatches over the already uploaded input and reads back the output.
+    pub async fn run_with_uniform(&self, uniform: &U) -> Result<Vec<O>, ComputeError> {
+        // Queue writes must be a multiple of COPY_BUFFER_ALIGNMENT
+        let mut bytes = bytemuck::bytes_of(uniform).to_vec();
+        bytes.resize(align::copy_size(bytes.len() as u64) as usize, 0);
+        self.context.queue.write_buffer(&self.uniform_buffer, 0, &bytes);
+
+        self.context
+            .dispatch(&self.compute_pipeline, &[&self.bind_group], self.dims);
+        self.context.read_buffer(&self.output_buffer, self.len).await
+    }
+}
@@ -0,0 +1,64 @@
+use bytemuck::Pod;
+
+use crate::{
+    context::storage_entries,
+    dispatch::{MAX_WORKGROUPS_PER_DIMENSION, WORKGROUP_SIZE},
+    ComputeError, GpuContext,
+};
+
+impl GpuContext {
+    /// Runs the kernel in place like `execute_kernel`, with the entry point `main_entry` only
+    /// dispatched over whole workgroups so it can skip bounds checks, and `tail_entry` over the
+    /// remaining partial workgroup, if any, as a single workgroup that does check.
+    ///
+    /// Both entry points get the data at binding 0 and a `DispatchInfo` at binding 1 whose
+    /// `offset` is the first element of their part, so element `offset + index(id)` is theirs.
+    /// For `main_entry` every invocation of the dispatch is in range. Inputs needing more
+    /// than `MAX_WORKGROUPS_PER_DIMENSION` workgroups take two main dispatches, one of whole
+    /// Y-packed rows and one for the workgroups left over.
+    pub async fn execute_kernel_with_tail<T: Pod>(
+        &self,
+        module: &wgpu::ShaderModule,
+        input: &[T],
+        main_entry: &str,
+        tail_entry: &str,
+    ) -> Result<Vec<T>, ComputeError> {
+        if input.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let bind_group_layout = self.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: None,
+            entries: &storage_entries(&[false]),
+        });
+        let storage_buffer = self.create_storage_buffer(input);
+
+        // Parts as (is tail, first element, element count). Splitting at whole Y-packed rows
+        // keeps every main dispatch free of invocations past its part
+        let workgroup_size = WORKGROUP_SIZE as usize;
+        let row_len = MAX_WORKGROUPS_PER_DIMENSION as usize * workgroup_size;
+        let aligned = input.len() / workgroup_size * workgroup_size;
+        let rows_end = aligned / row_len * row_len;
+        let mut parts = Vec::new();
+        if rows_end > 0 {
+            parts.push((false, 0, rows_end));
+        }
+        if aligned > rows_end {
+            parts.push((false, rows_end, aligned - rows_end));
+        }
+        if input.len() > aligned {
+            parts.push((true, aligned, input.len() - aligned));
+        }
+
+        let main_pipeline = self.create_pipeline_for_entry(module, &[&bind_group_layout], main_entry, 0);
+        let tail_pipeline = self.create_pipeline_for_entry(module, &[&bind_group_layout], tail_entry, 0);
+        for (is_tail, offset, len) in parts {
+            let (dims, info_buffer) = self.create_dispatch_info_at(len, WORKGROUP_SIZE, offset)?;
+            let bind_group = self.create_bind_group(&bind_group_layout, &[&storage_buffer, &info_buffer]);
+            let compute_pipeline = if is_tail { &tail_pipeline } else { &main_pipeline };
+            self.dispatch(compute_pipeline, &[&bind_group], dims);
+        }
+
+        self.read_buffer(&storage_buffer, input.len()).await
+    }
+}
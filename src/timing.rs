@@ -0,0 +1,76 @@
+use crate::{ComputeError, GpuContext};
+
+/// Timestamp queries written at the start and end of one compute pass.
+#[derive(Clone, Copy, Debug)]
+pub struct TimestampWrites<'a> {
+    pub query_set: &'a wgpu::QuerySet,
+    pub beginning_index: u32,
+    pub end_index: u32,
+}
+
+/// Per-pass settings, so each pass of a multi-pass workload can be told apart in captures and
+/// timed on its own.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PassDescriptor<'a> {
+    pub label: Option<&'a str>,
+    /// Requires a context created with `Features::TIMESTAMP_QUERY`.
+    pub timestamp_writes: Option<TimestampWrites<'a>>,
+}
+
+impl GpuContext {
+    /// Creates a set of `count` timestamp queries for `PassDescriptor::timestamp_writes`.
+    pub fn create_timestamp_query_set(&self, count: u32) -> Result<wgpu::QuerySet, ComputeError> {
+        if !self.device.features().contains(wgpu::Features::TIMESTAMP_QUERY) {
+            return Err(ComputeError::UnsupportedFeatures(wgpu::Features::TIMESTAMP_QUERY));
+        }
+        Ok(self.device.create_query_set(&wgpu::QuerySetDescriptor {
+            ty: wgpu::QueryType::Timestamp,
+            count,
+        }))
+    }
+
+    /// Nanoseconds per timestamp tick on this context's queue. It differs between adapters, so
+    /// convert raw timestamps with this rather than assuming a fixed period.
+    pub fn timestamp_period(&self) -> f32 {
+        self.queue.get_timestamp_period()
+    }
+
+    /// Reads back the first `count` raw timestamps of `query_set`. Multiply differences by
+    /// `timestamp_period` to get nanoseconds.
+    pub async fn read_timestamps(&self, query_set: &wgpu::QuerySet, count: u32) -> Result<Vec<u64>, ComputeError> {
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+
+        // Query results can only be resolved into a buffer, not mapped directly
+        let resolve_buffer = self.create_output_buffer::<u64>(count as usize);
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        encoder.resolve_query_set(query_set, 0..count, &resolve_buffer, 0);
+        self.queue.submit(Some(encoder.finish()));
+        self.read_buffer(&resolve_buffer, count as usize).await
+    }
+
+    /// Records one compute pass into `encoder` as described by `pass`, with `f` setting up and
+    /// dispatching inside it. The label also names a debug group around the pass contents.
+    pub(crate) fn record_pass<'a>(
+        &self,
+        encoder: &'a mut wgpu::CommandEncoder,
+        pass: &PassDescriptor<'a>,
+        f: impl FnOnce(&mut wgpu::ComputePass<'a>),
+    ) {
+        let mut cpass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: pass.label });
+        if let Some(timestamps) = pass.timestamp_writes {
+            cpass.write_timestamp(timestamps.query_set, timestamps.beginning_index);
+        }
+        if let Some(label) = pass.label {
+            cpass.push_debug_group(label);
+        }
+        f(&mut cpass);
+        if pass.label.is_some() {
+            cpass.pop_debug_group();
+        }
+        if let Some(timestamps) = pass.timestamp_writes {
+            cpass.write_timestamp(timestamps.query_set, timestamps.end_index);
+        }
+    }
+}
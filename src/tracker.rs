@@ -0,0 +1,71 @@
+use std::{
+    ops::Deref,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+/// Counts buffers created and dropped through a `GpuContext`, plus buffers left mapped.
+/// Leaks are only reported in debug builds.
+#[derive(Debug, Default)]
+pub(crate) struct ResourceTracker {
+    created: AtomicUsize,
+    dropped: AtomicUsize,
+    mapped: AtomicUsize,
+}
+
+impl ResourceTracker {
+    pub(crate) fn track(self: &Arc<Self>, buffer: wgpu::Buffer) -> TrackedBuffer {
+        self.created.fetch_add(1, Ordering::Relaxed);
+        TrackedBuffer {
+            buffer,
+            tracker: self.clone(),
+        }
+    }
+
+    pub(crate) fn mapped(&self) {
+        self.mapped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn unmapped(&self) {
+        self.mapped.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    /// Logs a warning for any buffers still alive or still mapped.
+    pub(crate) fn report_leaks(&self) {
+        if !cfg!(debug_assertions) {
+            return;
+        }
+        let created = self.created.load(Ordering::Relaxed);
+        let dropped = self.dropped.load(Ordering::Relaxed);
+        let mapped = self.mapped.load(Ordering::Relaxed);
+        if created > dropped {
+            log::warn!("GpuContext dropped with {} buffer(s) still alive", created - dropped);
+        }
+        if mapped > 0 {
+            log::warn!("GpuContext dropped with {} buffer(s) still mapped", mapped);
+        }
+    }
+}
+
+/// A `wgpu::Buffer` created through a `GpuContext`, reported to its tracker when dropped.
+#[derive(Debug)]
+pub struct TrackedBuffer {
+    buffer: wgpu::Buffer,
+    tracker: Arc<ResourceTracker>,
+}
+
+impl Deref for TrackedBuffer {
+    type Target = wgpu::Buffer;
+
+    fn deref(&self) -> &wgpu::Buffer {
+        &self.buffer
+    }
+}
+
+impl Drop for TrackedBuffer {
+    fn drop(&mut self) {
+        self.tracker.dropped.fetch_add(1, Ordering::Relaxed);
+    }
+}
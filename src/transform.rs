@@ -0,0 +1,65 @@
+use bytemuck::Pod;
+
+use shared::glam::Mat4;
+
+use crate::{
+    context::{storage_entries, uniform_layout_entry},
+    ComputeError, GpuContext,
+};
+
+// Four vec4 columns; already a multiple of the 16 bytes std140 rounds array strides up to
+const MAT4_STRIDE: usize = 64;
+
+/// Lays out `matrices` as a std140 uniform array: column-major, one 64-byte element per matrix
+/// with no padding in between. Goes through `to_cols_array` so the result doesn't depend on
+/// how glam stores `Mat4` on the host.
+pub fn std140_mat4_array(matrices: &[Mat4]) -> Vec<[f32; 16]> {
+    matrices.iter().map(Mat4::to_cols_array).collect()
+}
+
+impl GpuContext {
+    /// Runs the kernel in place over `input` like `execute_kernel`, with `transforms` bound as a
+    /// uniform array of `Mat4` at binding 2 after the `DispatchInfo` uniform, so each invocation
+    /// can transform its element by a matrix it selects.
+    ///
+    /// Uniform arrays can't be runtime-sized, so the kernel declares a fixed-size array, e.g.
+    /// `&[Mat4; 16]`, no longer than `transforms`. Fails with `ComputeError::BufferTooLarge` if
+    /// the matrices exceed the device's uniform binding limit, 256 of them by default.
+    pub async fn execute_kernel_transformed<T: Pod>(
+        &self,
+        module: &wgpu::ShaderModule,
+        input: &[T],
+        transforms: &[Mat4],
+    ) -> Result<Vec<T>, ComputeError> {
+        if input.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // Zero-sized buffers can't be bound, so an empty array still gets one zero matrix
+        let mut matrices = std140_mat4_array(transforms);
+        if matrices.is_empty() {
+            matrices.push([0.0; 16]);
+        }
+        let size = (matrices.len() * MAT4_STRIDE) as u64;
+        let max = self.device.limits().max_uniform_buffer_binding_size as u64;
+        if size > max {
+            return Err(ComputeError::BufferTooLarge { size, max });
+        }
+
+        let mut entries = storage_entries(&[false]);
+        entries.push(uniform_layout_entry(2, size));
+        let bind_group_layout = self.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: None,
+            entries: &entries,
+        });
+        let compute_pipeline = self.create_pipeline(module, &[&bind_group_layout]);
+
+        let storage_buffer = self.create_storage_buffer(input);
+        let (dims, info_buffer) = self.create_dispatch_info(input.len())?;
+        let transform_buffer = self.create_buffer_with_usage(&matrices, wgpu::BufferUsage::UNIFORM);
+        let bind_group = self.create_bind_group(&bind_group_layout, &[&storage_buffer, &info_buffer, &transform_buffer]);
+
+        self.dispatch(&compute_pipeline, &[&bind_group], dims);
+        self.read_buffer(&storage_buffer, input.len()).await
+    }
+}
@@ -0,0 +1,396 @@
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{shader_descriptor, ComputeError, GpuContext};
+
+// Entry point the bundled kernels use
+const DEFAULT_ENTRY_POINT: &str = "main_cs";
+
+/// The capabilities a deployment target is known to support. SPIR-V accepted by desktop drivers
+/// can still be rejected on mobile adapters, so shaders can be checked against a stricter
+/// profile before shipping.
+#[derive(Clone, Debug)]
+pub struct CapabilityProfile {
+    pub float64: bool,
+    pub push_constants: bool,
+    pub max_storage_buffers_per_shader_stage: u32,
+}
+
+impl CapabilityProfile {
+    /// Minimums guaranteed by Vulkan, which is what most Android drivers offer.
+    pub fn mobile() -> Self {
+        Self {
+            float64: false,
+            push_constants: false,
+            max_storage_buffers_per_shader_stage: 4,
+        }
+    }
+
+    pub fn desktop() -> Self {
+        Self {
+            float64: true,
+            push_constants: true,
+            max_storage_buffers_per_shader_stage: wgpu::Limits::default().max_storage_buffers_per_shader_stage,
+        }
+    }
+}
+
+/// Parses and validates a SPIR-V blob with naga, restricted to what `profile` supports.
+pub fn validate_spirv(spirv: &[u8], profile: &CapabilityProfile) -> Result<(), ComputeError> {
+    let options = naga::front::spv::Options {
+        strict_capabilities: true,
+        ..Default::default()
+    };
+    let module = naga::front::spv::parse_u8_slice(spirv, &options)
+        .map_err(|e| ComputeError::UnsupportedShaderFeature(format!("{:?}", e)))?;
+
+    let mut capabilities = naga::valid::Capabilities::empty();
+    capabilities.set(naga::valid::Capabilities::FLOAT64, profile.float64);
+    capabilities.set(naga::valid::Capabilities::PUSH_CONSTANT, profile.push_constants);
+    naga::valid::Validator::new(naga::valid::ValidationFlags::all(), capabilities)
+        .validate(&module)
+        .map_err(|e| ComputeError::UnsupportedShaderFeature(e.to_string()))?;
+
+    // Descriptor limits aren't covered by naga's capabilities
+    let storage_buffers = module
+        .global_variables
+        .iter()
+        .filter(|(_, var)| var.class == naga::StorageClass::Storage)
+        .count() as u32;
+    if storage_buffers > profile.max_storage_buffers_per_shader_stage {
+        return Err(ComputeError::UnsupportedShaderFeature(format!(
+            "shader uses {} storage buffers but the profile allows {}",
+            storage_buffers, profile.max_storage_buffers_per_shader_stage
+        )));
+    }
+
+    Ok(())
+}
+
+const OP_DECORATE: u32 = 71;
+const DECORATION_FP_FAST_MATH_MODE: u32 = 40;
+
+/// Rejects SPIR-V that opts into fast-math float semantics via `FPFastMathMode` decorations,
+/// for users who need bit-identical results across runs.
+///
+/// This only covers what the module itself asks for. wgpu has no way to ask drivers for strict
+/// float semantics, so a backend compiler may still fuse or reorder operations, and results
+/// are only reproducible on the same adapter and driver.
+pub fn check_deterministic(spirv: &[u8]) -> Result<(), ComputeError> {
+    check_deterministic_words(&crate::spirv_words(spirv)?)
+}
+
+pub(crate) fn check_deterministic_words(words: &[u32]) -> Result<(), ComputeError> {
+    // Instructions follow the five word header, each starting with its word count and opcode
+    let mut offset = 5;
+    while offset < words.len() {
+        let word_count = (words[offset] >> 16) as usize;
+        let opcode = words[offset] & 0xffff;
+        if word_count == 0 || offset + word_count > words.len() {
+            return Err(ComputeError::InvalidSpirv(format!("bad instruction at word {}", offset)));
+        }
+        if opcode == OP_DECORATE && word_count >= 3 && words[offset + 2] == DECORATION_FP_FAST_MATH_MODE {
+            return Err(ComputeError::UnsupportedShaderFeature(format!(
+                "id {} is decorated with FPFastMathMode",
+                words[offset + 1]
+            )));
+        }
+        offset += word_count;
+    }
+    Ok(())
+}
+
+/// Lists the workgroup (shared) variables an entry point of `spirv` may load before storing to
+/// them. Workgroup memory starts out undefined, so kernels have to zero it themselves, followed
+/// by a barrier, before reading it; see `kernels/workgroup_sum`.
+///
+/// The check is conservative about control flow: a store only counts once it happens on every
+/// path, so stores inside loops or only one branch of an `if` don't. Loads through function
+/// calls are followed, loads through pointers passed as arguments are not.
+pub fn uninitialized_workgroup_reads(spirv: &[u8]) -> Result<Vec<String>, ComputeError> {
+    let module = naga::front::spv::parse_u8_slice(spirv, &Default::default())
+        .map_err(|e| ComputeError::UnsupportedShaderFeature(format!("{:?}", e)))?;
+
+    let mut flagged = HashSet::new();
+    for entry_point in &module.entry_points {
+        let mut written = HashSet::new();
+        scan_block(&module, &entry_point.function, &entry_point.function.body, &mut written, &mut flagged);
+    }
+
+    let mut names = flagged
+        .into_iter()
+        .map(|handle| match &module.global_variables[handle].name {
+            Some(name) => name.clone(),
+            None => format!("workgroup variable {}", handle.index()),
+        })
+        .collect::<Vec<_>>();
+    names.sort();
+    Ok(names)
+}
+
+type GlobalHandle = naga::Handle<naga::GlobalVariable>;
+
+// Walk statements in order, recording workgroup stores and flagging loads that come first
+fn scan_block(
+    module: &naga::Module,
+    function: &naga::Function,
+    block: &naga::Block,
+    written: &mut HashSet<GlobalHandle>,
+    flagged: &mut HashSet<GlobalHandle>,
+) {
+    for statement in block.iter() {
+        match statement {
+            naga::Statement::Emit(range) => {
+                for handle in range.clone() {
+                    if let naga::Expression::Load { pointer } = function.expressions[handle] {
+                        if let Some(global) = workgroup_root(module, function, pointer) {
+                            if !written.contains(&global) {
+                                flagged.insert(global);
+                            }
+                        }
+                    }
+                }
+            }
+            naga::Statement::Store { pointer, .. } => {
+                if let Some(global) = workgroup_root(module, function, *pointer) {
+                    written.insert(global);
+                }
+            }
+            naga::Statement::Block(inner) => scan_block(module, function, inner, written, flagged),
+            naga::Statement::If { accept, reject, .. } => {
+                let mut accepted = written.clone();
+                let mut rejected = written.clone();
+                scan_block(module, function, accept, &mut accepted, flagged);
+                scan_block(module, function, reject, &mut rejected, flagged);
+                *written = accepted.intersection(&rejected).copied().collect();
+            }
+            // May run zero times, so nothing stored inside counts afterwards
+            naga::Statement::Loop { body, continuing } => {
+                let mut inner = written.clone();
+                scan_block(module, function, body, &mut inner, flagged);
+                scan_block(module, function, continuing, &mut inner, flagged);
+            }
+            naga::Statement::Switch { cases, default, .. } => {
+                for case in cases {
+                    scan_block(module, function, &case.body, &mut written.clone(), flagged);
+                }
+                scan_block(module, function, default, &mut written.clone(), flagged);
+            }
+            naga::Statement::Call { function: callee, .. } => {
+                let callee = &module.functions[*callee];
+                scan_block(module, callee, &callee.body, written, flagged);
+            }
+            _ => {}
+        }
+    }
+}
+
+// The workgroup variable a pointer expression points into, if any
+fn workgroup_root(
+    module: &naga::Module,
+    function: &naga::Function,
+    mut pointer: naga::Handle<naga::Expression>,
+) -> Option<GlobalHandle> {
+    loop {
+        match function.expressions[pointer] {
+            naga::Expression::Access { base, .. } | naga::Expression::AccessIndex { base, .. } => pointer = base,
+            naga::Expression::GlobalVariable(global) => {
+                return Some(global).filter(|global| module.global_variables[*global].class == naga::StorageClass::WorkGroup)
+            }
+            _ => return None,
+        }
+    }
+}
+
+impl GpuContext {
+    /// Creates a shader module and a pipeline for `entry_point` from `spirv` on this context's
+    /// device without dispatching anything. A rejected module fails with
+    /// `ComputeError::InvalidSpirv` and a rejected pipeline with `ComputeError::PipelineCreation`,
+    /// each carrying the device's message. The pipeline layout is derived from the shader.
+    pub async fn validate_shader(&self, spirv: &[u8], entry_point: &str) -> Result<(), ComputeError> {
+        let module = self.try_create_shader_module(&shader_descriptor(spirv)?).await?;
+
+        self.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let _ = self.device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: None,
+            layout: None,
+            module: &module,
+            entry_point,
+        });
+        match self.device.pop_error_scope().await {
+            Some(e) => Err(ComputeError::PipelineCreation(e.to_string())),
+            None => Ok(()),
+        }
+    }
+
+    /// Runs `validate_shader` on every `.spv` file in `dir`, sorted by path, with the entry
+    /// point the bundled kernels use. Only failing to list `dir` is an error; per-file failures,
+    /// including unreadable files, are returned alongside their paths.
+    pub async fn validate_dir(&self, dir: &Path) -> Result<Vec<(PathBuf, Result<(), ComputeError>)>, ComputeError> {
+        let mut paths = fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().map_or(false, |extension| extension == "spv"))
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        let mut results = Vec::with_capacity(paths.len());
+        for path in paths {
+            let result = match fs::read(&path) {
+                Ok(spirv) => self.validate_shader(&spirv, DEFAULT_ENTRY_POINT).await,
+                Err(e) => Err(e.into()),
+            };
+            results.push((path, result));
+        }
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OP_NAME: u32 = 5;
+    const OP_MEMORY_MODEL: u32 = 14;
+    const OP_ENTRY_POINT: u32 = 15;
+    const OP_EXECUTION_MODE: u32 = 16;
+    const OP_CAPABILITY: u32 = 17;
+    const OP_TYPE_VOID: u32 = 19;
+    const OP_TYPE_INT: u32 = 21;
+    const OP_TYPE_FLOAT: u32 = 22;
+    const OP_TYPE_POINTER: u32 = 32;
+    const OP_TYPE_FUNCTION: u32 = 33;
+    const OP_CONSTANT: u32 = 43;
+    const OP_FUNCTION: u32 = 54;
+    const OP_FUNCTION_END: u32 = 56;
+    const OP_VARIABLE: u32 = 59;
+    const OP_LOAD: u32 = 61;
+    const OP_STORE: u32 = 62;
+    const OP_LABEL: u32 = 248;
+    const OP_RETURN: u32 = 253;
+
+    const CAPABILITY_SHADER: u32 = 1;
+    const CAPABILITY_FLOAT64: u32 = 10;
+    const STORAGE_CLASS_WORKGROUP: u32 = 4;
+
+    fn instruction(words: &mut Vec<u32>, opcode: u32, operands: &[u32]) {
+        words.push(((operands.len() as u32 + 1) << 16) | opcode);
+        words.extend_from_slice(operands);
+    }
+
+    // Nul-terminated and padded to whole words, lowest byte first
+    fn string(s: &str) -> Vec<u32> {
+        let mut bytes = s.as_bytes().to_vec();
+        bytes.resize((bytes.len() / 4 + 1) * 4, 0);
+        bytes.chunks(4).map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]])).collect()
+    }
+
+    fn blob(words: &[u32], bound: u32) -> Vec<u8> {
+        [0x0723_0203, 0x0001_0300, 0, bound, 0]
+            .iter()
+            .chain(words)
+            .flat_map(|word| word.to_ne_bytes().to_vec())
+            .collect()
+    }
+
+    // A compute entry point that touches the workgroup `uint` "shared", storing 0 to it either
+    // before or after loading it. With `float64`, also declares a 64-bit float type.
+    fn kernel(store_first: bool, float64: bool) -> Vec<u8> {
+        let (void, function_type, uint, pointer, shared, zero, main, label, loaded, double) = (1, 2, 3, 4, 5, 6, 7, 8, 9, 10);
+        let mut words = Vec::new();
+        instruction(&mut words, OP_CAPABILITY, &[CAPABILITY_SHADER]);
+        if float64 {
+            instruction(&mut words, OP_CAPABILITY, &[CAPABILITY_FLOAT64]);
+        }
+        instruction(&mut words, OP_MEMORY_MODEL, &[0, 1]);
+        // GLCompute with a LocalSize of 1, 1, 1
+        let mut entry_point = vec![5, main];
+        entry_point.extend(string("main"));
+        instruction(&mut words, OP_ENTRY_POINT, &entry_point);
+        instruction(&mut words, OP_EXECUTION_MODE, &[main, 17, 1, 1, 1]);
+        let mut name = vec![shared];
+        name.extend(string("shared"));
+        instruction(&mut words, OP_NAME, &name);
+        instruction(&mut words, OP_TYPE_VOID, &[void]);
+        instruction(&mut words, OP_TYPE_FUNCTION, &[function_type, void]);
+        instruction(&mut words, OP_TYPE_INT, &[uint, 32, 0]);
+        if float64 {
+            instruction(&mut words, OP_TYPE_FLOAT, &[double, 64]);
+        }
+        instruction(&mut words, OP_TYPE_POINTER, &[pointer, STORAGE_CLASS_WORKGROUP, uint]);
+        instruction(&mut words, OP_VARIABLE, &[pointer, shared, STORAGE_CLASS_WORKGROUP]);
+        instruction(&mut words, OP_CONSTANT, &[uint, zero, 0]);
+        instruction(&mut words, OP_FUNCTION, &[void, main, 0, function_type]);
+        instruction(&mut words, OP_LABEL, &[label]);
+        if store_first {
+            instruction(&mut words, OP_STORE, &[shared, zero]);
+            instruction(&mut words, OP_LOAD, &[uint, loaded, shared]);
+        } else {
+            instruction(&mut words, OP_LOAD, &[uint, loaded, shared]);
+            instruction(&mut words, OP_STORE, &[shared, zero]);
+        }
+        instruction(&mut words, OP_RETURN, &[]);
+        instruction(&mut words, OP_FUNCTION_END, &[]);
+        blob(&words, 11)
+    }
+
+    #[test]
+    fn deterministic_rejects_fast_math() {
+        let mut words = Vec::new();
+        instruction(&mut words, OP_DECORATE, &[1, DECORATION_FP_FAST_MATH_MODE, 0x10]);
+        assert!(matches!(
+            check_deterministic(&blob(&words, 2)),
+            Err(ComputeError::UnsupportedShaderFeature(_))
+        ));
+    }
+
+    #[test]
+    fn deterministic_accepts_other_decorations() {
+        // RelaxedPrecision
+        let mut words = Vec::new();
+        instruction(&mut words, OP_DECORATE, &[1, 0]);
+        assert!(check_deterministic(&blob(&words, 2)).is_ok());
+        assert!(check_deterministic(&kernel(true, false)).is_ok());
+    }
+
+    #[test]
+    fn deterministic_rejects_truncated_instruction() {
+        let mut words = Vec::new();
+        instruction(&mut words, OP_DECORATE, &[1, DECORATION_FP_FAST_MATH_MODE, 0x10]);
+        words.pop();
+        assert!(matches!(check_deterministic(&blob(&words, 2)), Err(ComputeError::InvalidSpirv(_))));
+    }
+
+    #[test]
+    fn workgroup_load_before_store() {
+        assert_eq!(uninitialized_workgroup_reads(&kernel(false, false)).unwrap(), vec!["shared".to_string()]);
+    }
+
+    #[test]
+    fn workgroup_load_after_store() {
+        assert!(uninitialized_workgroup_reads(&kernel(true, false)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn validate_spirv_profiles() {
+        let spirv = kernel(true, false);
+        assert!(validate_spirv(&spirv, &CapabilityProfile::mobile()).is_ok());
+        assert!(validate_spirv(&spirv, &CapabilityProfile::desktop()).is_ok());
+
+        let float64 = kernel(true, true);
+        assert!(validate_spirv(&float64, &CapabilityProfile::desktop()).is_ok());
+        assert!(matches!(
+            validate_spirv(&float64, &CapabilityProfile::mobile()),
+            Err(ComputeError::UnsupportedShaderFeature(_))
+        ));
+    }
+
+    #[test]
+    fn validate_spirv_rejects_bad_magic() {
+        let mut spirv = kernel(true, false);
+        spirv[0] ^= 0xff;
+        assert!(validate_spirv(&spirv, &CapabilityProfile::desktop()).is_err());
+    }
+}
@@ -0,0 +1,53 @@
+//! Checks the raytrace kernel against the CPU intersection it shares with the host through
+//! `shared::math`. Needs a GPU adapter, so run with `cargo test -- --ignored`.
+
+use rustic::{shader_descriptor, GpuContext};
+use shared::{
+    glam::{const_vec3, Vec3, Vec4},
+    math::intersect_sphere,
+    Ray,
+};
+
+const KERNEL: &[u8] = include_bytes!(env!("raytrace.spv"));
+
+// Must match the sphere hardcoded in kernels/raytrace
+const SPHERE_CENTER: Vec3 = const_vec3!([0.0, 0.0, -3.0]);
+const SPHERE_RADIUS: f32 = 1.0;
+
+const WIDTH: usize = 16;
+const HEIGHT: usize = 16;
+
+#[test]
+#[ignore = "needs a GPU adapter"]
+fn gpu_hits_match_cpu() {
+    // Orthographic rays looking down -Z over the square [-2, 2] x [-2, 2]
+    let rays = (0..WIDTH * HEIGHT)
+        .map(|i| {
+            let x = ((i % WIDTH) as f32 + 0.5) / WIDTH as f32 * 4.0 - 2.0;
+            let y = ((i / WIDTH) as f32 + 0.5) / HEIGHT as f32 * 4.0 - 2.0;
+            Ray {
+                origin: Vec4::new(x, y, 0.0, 0.0),
+                direction: Vec4::new(0.0, 0.0, -1.0, 0.0),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let hits = futures::executor::block_on(async {
+        let context = GpuContext::new().await?;
+        let module = context.try_create_shader_module(&shader_descriptor(KERNEL)?).await?;
+        context
+            .execute_kernel_with_output::<Ray, f32>(&module, &rays, rays.len())
+            .await
+    })
+    .unwrap();
+
+    let mismatches = rays
+        .iter()
+        .zip(&hits)
+        .enumerate()
+        .filter(|(_, (ray, gpu))| (intersect_sphere(ray, SPHERE_CENTER, SPHERE_RADIUS) - **gpu).abs() > 1e-4)
+        .map(|(index, _)| index)
+        .collect::<Vec<_>>();
+    assert_eq!(hits.len(), rays.len());
+    assert!(mismatches.is_empty(), "rays {:?} differ from the CPU reference", mismatches);
+}